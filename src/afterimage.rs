@@ -0,0 +1,139 @@
+use crate::palette::mix;
+use crate::palette::Color;
+use crate::palette::Palette;
+
+// How pixels erased by XOR linger on screen.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Persistence {
+  Off,      // show each frame as is
+  Or,       // light pixels lit in this or the last frame
+  Phosphor, // fade erased pixels out like a CRT phosphor
+  Blend,    // average the colors of this and the last frame
+}
+
+impl Persistence {
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    match input.trim() {
+      "off" => Ok(Self::Off),
+      "or" => Ok(Self::Or),
+      "phosphor" => Ok(Self::Phosphor),
+      "blend" => Ok(Self::Blend),
+      _ => Err("Invalid Persistence"),
+    }
+  }
+}
+
+// Display post-processing that hides the flicker of sprites erased and redrawn
+// between frames. Works on a copy of each frame - the display of the
+// interpreter is never modified.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Afterimage {
+  persistence: Persistence,
+  decay: u8,         // percent of phosphor intensity lost per frame
+  previous: Vec<u8>, // display of the last frame
+  levels: Vec<u8>,   // phosphor intensity of each pixel
+  planes: Vec<u8>,   // planes each pixel was last lit in
+  colors: Vec<Color>,
+}
+
+impl Afterimage {
+  pub fn new(persistence: Persistence, decay: u8) -> Self {
+    Self {
+      persistence,
+      decay: decay.min(100),
+      previous: Vec::new(),
+      levels: Vec::new(),
+      planes: Vec::new(),
+      colors: Vec::new(),
+    }
+  }
+
+  // Returns true if frames depend on earlier frames and must be drawn even if
+  // the display is unchanged.
+  #[inline]
+  pub fn is_active(&self) -> bool {
+    self.persistence != Persistence::Off
+  }
+
+  // Returns the color of every display pixel for the next frame.
+  pub fn frame(&mut self, display: &[u8], palette: &Palette) -> &[Color] {
+    if self.previous.len() != display.len() {
+      self.previous = display.to_vec();
+      self.levels = vec![0; display.len()];
+      self.planes = display.to_vec();
+      self.colors = vec![palette.background; display.len()];
+    }
+
+    for (index, &value) in display.iter().enumerate() {
+      let previous: u8 = self.previous[index];
+
+      self.colors[index] = match self.persistence {
+        Persistence::Off => palette.pixel(value),
+        Persistence::Or => palette.pixel(value | previous),
+        Persistence::Blend => mix(palette.pixel(previous), palette.pixel(value), 128),
+        Persistence::Phosphor => {
+          if value != 0 {
+            self.levels[index] = 255;
+            self.planes[index] = value;
+          } else {
+            let level: u16 = self.levels[index] as u16 * (100 - self.decay) as u16 / 100;
+
+            self.levels[index] = level as u8;
+          }
+
+          let lit: Color = palette.pixel(self.planes[index]);
+
+          mix(palette.background, lit, self.levels[index])
+        }
+      };
+    }
+
+    self.previous.copy_from_slice(display);
+
+    &self.colors
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_or_keeps_previous_frame() {
+    let palette: Palette = Palette::new();
+    let (white, black): (Color, Color) = (palette.foreground, palette.background);
+    let mut or: Afterimage = Afterimage::new(Persistence::Or, 0);
+
+    assert_eq!(or.frame(&[1, 0], &palette), &[white, black]);
+    assert_eq!(or.frame(&[0, 1], &palette), &[white, white]);
+    assert_eq!(or.frame(&[0, 1], &palette), &[black, white]);
+  }
+
+  #[test]
+  fn test_phosphor_decays() {
+    let palette: Palette = Palette::new();
+    let white: Color = palette.foreground;
+    let mut phosphor: Afterimage = Afterimage::new(Persistence::Phosphor, 50);
+
+    assert_eq!(phosphor.frame(&[1], &palette), &[white]);
+    assert_eq!(phosphor.frame(&[0], &palette), &[(127, 127, 127)]);
+    assert_eq!(phosphor.frame(&[0], &palette), &[(63, 63, 63)]);
+    assert_eq!(phosphor.frame(&[1], &palette), &[white]);
+  }
+
+  #[test]
+  fn test_blend_averages_frames() {
+    let palette: Palette = Palette::new();
+    let mut blend: Afterimage = Afterimage::new(Persistence::Blend, 0);
+
+    blend.frame(&[1], &palette);
+
+    assert_eq!(blend.frame(&[0], &palette), &[(127, 127, 127)]);
+  }
+
+  #[test]
+  fn test_parse_persistence() {
+    assert_eq!(Persistence::parse("crt"), Err("Invalid Persistence"));
+  }
+}
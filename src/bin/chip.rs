@@ -1,59 +1,352 @@
 use std::env::args;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::exit;
 
+use chip::parse_bool;
+use chip::parse_number;
+use chip::parse_scale;
 use chip::AudioBell;
+use chip::Browser;
+use chip::Chip8;
 use chip::ChipRunner;
+use chip::Config;
+use chip::GdbStub;
+use chip::Ini;
+use chip::Keymap;
+use chip::Layout;
 use chip::Mode;
+use chip::Overrides;
+use chip::PixelFormat;
+use chip::Profiler;
+use chip::Quirks;
 use chip::Renderer;
 use chip::RendererFlags;
 use chip::SDLToken;
 use chip::SDL_Context;
 use chip::Surface;
+use chip::Symbols;
 use chip::Texture;
+use chip::TextureAccess;
+use chip::Tracer;
 use chip::Window;
 use chip::WindowFlags;
 
 static FONT_BMP: &[u8] = include_bytes!("font.bmp");
 
+const USAGE: &str = "\
+Usage: chip [OPTIONS] ROM
+
+Options:
+  --mode=MODE              interpreter to emulate: chip or schip (default: detected)
+  --speed=N                instructions per second
+  --quirk=NAME=on|off      toggle a quirk: shift, memory, jump, logic or overflow
+  --seed=N                 seed of the random number generator
+  --scale=N                size of a low-res pixel in the play layout, 1 to 32
+  --layout=LAYOUT          debug or play - switch with L (default: debug)
+  --fullscreen             cover the screen - toggle with F11
+  --roms=DIR               directory listed by the ROM browser - open with B
+                           (default: roms)
+  --start-address=ADDR     load address and entry point of the ROM (default: 0x200)
+  --eti                    same as --start-address=0x600
+  --paused                 start halted - resume with SPACE
+  --journal                record instructions so they can be stepped back
+  --headless               run without a window (use with --gdb)
+  --config=PATH            config file, also stores the theme picked with P
+                           (default: ~/.config/chip/chip.ini) - recent ROMs
+                           are kept in a `recent` file next to it
+  --keymap=PATH            keymap file
+  --bind=ACTION=KEYS       bind host keys to an action, ie. --bind=5=w,up
+  --symbols=PATH           symbol file with labels, breakpoints and monitors
+  --gdb=PORT               wait for a GDB remote debugger on PORT
+  --trace=PATH|-           log every executed instruction
+  --trace-range=START-END  only log instructions within the address range
+  --profile                print an execution profile on exit
+  --coverage=PATH          write memory coverage as lcov, or JSON for *.json
+  -h, --help               print this message
+";
+
+const START: u16 = 0x200;
+const START_ETI: u16 = 0x600;
+const END: u16 = 0xFFF; // first address past the end of the memory ROMs load into
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Args {
-  pub eti: bool,
-  pub mode: Mode,
+  pub help: bool,
+  pub headless: bool,
+  pub paused: bool,
+  pub profile: bool,
+  pub journal: bool,
+  pub coverage: Option<String>,
+  pub gdb: Option<u16>,
+  pub symbols: Option<String>,
+  pub trace: Option<String>,
+  pub trace_range: Option<String>,
+  pub config: Option<String>,
+  pub keymap: Option<String>,
+  pub bindings: Vec<String>,
+  pub speed: Option<u64>,
+  pub quirks: Vec<(Quirks, bool)>,
+  pub seed: Option<u32>,
+  pub scale: Option<u32>,
+  pub layout: Option<Layout>,
+  pub fullscreen: bool,
+  pub roms: Option<String>,
+  pub start: u16,
+  pub mode: Option<Mode>,
   pub rom: String,
 }
 
 impl Args {
-  pub fn from_env() -> Self {
+  pub fn from_env() -> Result<Self, String> {
+    Self::parse(args().skip(1))
+  }
+
+  // Parses options given as `--name=value` or `--name value`.
+  pub fn parse<I: Iterator<Item = String>>(mut input: I) -> Result<Self, String> {
     let mut data: Self = Self {
-      eti: false,
-      mode: Mode::CHIP,
+      help: false,
+      headless: false,
+      paused: false,
+      profile: false,
+      journal: false,
+      coverage: None,
+      gdb: None,
+      symbols: None,
+      trace: None,
+      trace_range: None,
+      config: None,
+      keymap: None,
+      bindings: Vec::new(),
+      speed: None,
+      quirks: Vec::new(),
+      seed: None,
+      scale: None,
+      layout: None,
+      fullscreen: false,
+      roms: None,
+      start: START,
+      mode: None,
       rom: String::new(),
     };
 
-    for arg in args() {
-      match arg.as_str() {
-        "--eti" => data.eti = true,
-        "--chip" => data.mode = Mode::CHIP,
-        "--schip" => data.mode = Mode::SCHIP,
-        _ => data.rom = arg,
+    while let Some(arg) = input.next() {
+      let (name, inline): (&str, Option<&str>) = match arg.find('=') {
+        Some(index) if arg.starts_with("--") => (&arg[..index], Some(&arg[index + 1..])),
+        _ => (arg.as_str(), None),
+      };
+
+      let mut value = || -> Result<String, String> {
+        match inline {
+          Some(value) => Ok(value.to_string()),
+          None => input.next().ok_or_else(|| format!("{}: missing value", name)),
+        }
+      };
+
+      match name {
+        "-h" | "--help" => data.help = true,
+        "--headless" => data.headless = true,
+        "--paused" => data.paused = true,
+        "--fullscreen" => data.fullscreen = true,
+        "--profile" => data.profile = true,
+        "--journal" => data.journal = true,
+        "--eti" => data.start = START_ETI,
+        "--chip" => data.mode = Some(Mode::CHIP),
+        "--schip" => data.mode = Some(Mode::SCHIP),
+        "--mode" => data.mode = Some(parse_mode(&value()?)?),
+        "--speed" => data.speed = Some(parse_speed(&value()?)?),
+        "--quirk" => data.quirks.push(parse_quirk(&value()?)?),
+        "--seed" => data.seed = Some(parse_value(name, &value()?)?),
+        "--scale" => {
+          let scale: String = value()?;
+
+          data.scale = Some(
+            parse_scale(&scale)
+              .ok_or_else(|| format!("--scale: {}: expected a number from 1 to 32", scale))?,
+          );
+        }
+        "--layout" => {
+          let layout: String = value()?;
+
+          data.layout = Some(
+            Layout::parse(&layout)
+              .map_err(|_| format!("--layout: {}: expected debug or play", layout))?,
+          );
+        }
+        "--roms" => data.roms = Some(value()?),
+        "--start-address" => data.start = parse_address(&value()?)?,
+        "--gdb" => data.gdb = Some(parse_value(name, &value()?)?),
+        "--symbols" => data.symbols = Some(value()?),
+        "--coverage" => data.coverage = Some(value()?),
+        "--trace" => data.trace = Some(value()?),
+        "--trace-range" => data.trace_range = Some(value()?),
+        "--config" => data.config = Some(value()?),
+        "--keymap" => data.keymap = Some(value()?),
+        "--bind" => data.bindings.push(value()?),
+        _ if name.starts_with('-') && name != "-" => {
+          return Err(format!("{}: unknown option", name));
+        }
+        _ if !data.rom.is_empty() => {
+          return Err(format!("{}: unexpected argument, ROM is {}", arg, data.rom));
+        }
+        _ => data.rom = arg.clone(),
       }
     }
 
-    data
+    if data.rom.is_empty() && !data.help {
+      return Err("missing ROM".to_string());
+    }
+
+    if data.headless && data.gdb.is_none() && !data.help {
+      return Err("--headless: requires --gdb to stop the run".to_string());
+    }
+
+    Ok(data)
+  }
+}
+
+fn parse_mode(input: &str) -> Result<Mode, String> {
+  match input.to_ascii_lowercase().as_str() {
+    "xo" | "xochip" | "xo-chip" => Err(format!("--mode: {}: Unsupported Mode", input)),
+    _ => Mode::parse(input).map_err(|_| format!("--mode: {}: expected chip or schip", input)),
   }
 }
 
-fn main() -> Result<(), &'static str> {
-  let args: Args = Args::from_env();
+fn parse_quirk(input: &str) -> Result<(Quirks, bool), String> {
+  let (name, value): (&str, &str) = match input.find('=') {
+    Some(index) => (&input[..index], &input[index + 1..]),
+    None => (input, "on"),
+  };
+
+  let quirk: Quirks =
+    Quirks::from_name(name).ok_or_else(|| format!("--quirk: {}: unknown quirk", name))?;
+  let value: bool =
+    parse_bool(value).ok_or_else(|| format!("--quirk: {}: expected on or off", value))?;
+
+  Ok((quirk, value))
+}
+
+fn parse_address(input: &str) -> Result<u16, String> {
+  parse_number(input)
+    .filter(|address| (START..END).contains(address))
+    .ok_or_else(|| {
+      format!(
+        "--start-address: {}: expected an address from {:#05X} to {:#05X}",
+        input,
+        START,
+        END - 1
+      )
+    })
+}
+
+fn parse_speed(input: &str) -> Result<u64, String> {
+  input
+    .parse()
+    .ok()
+    .filter(|speed| *speed > 0)
+    .ok_or_else(|| format!("--speed: {}: expected a positive number", input))
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, input: &str) -> Result<T, String> {
+  input
+    .parse()
+    .map_err(|_| format!("{}: {}: expected a number", name, input))
+}
+
+fn main() {
+  let args: Args = match Args::from_env() {
+    Ok(args) => args,
+    Err(error) => {
+      eprintln!("chip: {}\n\n{}", error, USAGE);
+      exit(2);
+    }
+  };
+
+  if args.help {
+    print!("{}", USAGE);
+    return;
+  }
+
+  if let Err(error) = run(args) {
+    eprintln!("chip: {}", error);
+    exit(1);
+  }
+}
+
+fn run(args: Args) -> Result<(), String> {
+  let mut runner: ChipRunner = ChipRunner::new();
+
+  if let Some(seed) = args.seed {
+    Chip8::seed(seed);
+  }
+
+  runner.settings(load_settings(&args)?);
+  runner.overrides(load_overrides(&args)?);
+  runner
+    .load_at(&args.rom, args.start)
+    .map_err(|error| format!("{}: {}", args.rom, error))?;
+
+  let config: Config = runner.config().clone();
+
+  if args.paused {
+    runner.pause();
+  }
+
+  if let Some(path) = args.symbols.as_ref() {
+    runner.symbols(Symbols::load(path).map_err(|error| format!("{}: {}", path, error))?);
+  }
+
+  if let Some(path) = args.trace.as_ref() {
+    let mut tracer: Tracer = match path.as_str() {
+      "-" => Tracer::stdout(),
+      _ => Tracer::create(path).map_err(|error| format!("{}: {}", path, error))?,
+    };
+
+    if let Some(range) = args.trace_range.as_ref() {
+      tracer = tracer.range(
+        Tracer::parse_range(range).map_err(|error| format!("--trace-range: {}", error))?,
+      );
+    }
+
+    runner.trace(tracer);
+  }
+
+  if args.profile {
+    runner.profile(Profiler::new());
+  }
+
+  if let Some(port) = args.gdb {
+    runner.attach(GdbStub::bind(port).map_err(|_| format!("--gdb: {}: Invalid GDB Port", port))?);
+  }
+
+  if args.headless {
+    runner.run_headless();
+    return export_coverage(&runner, args.coverage.as_deref());
+  }
+
+  let mut browser: Browser = Browser::new(&config.roms);
+
+  if let Some(path) = config_file(&args) {
+    browser = browser.history(path.with_file_name("recent"));
+    runner.store(path);
+  }
+
+  let _ = browser.visit(&args.rom);
+
+  runner.browser(browser);
+
   let token: SDLToken = SDLToken::init()?;
 
+  let (w, h): (i32, i32) = runner.window_size();
+
   let window: Window = token.create_window(
-    "Chip-8\0",
+    runner.title(),
     Window::CENTERED_MASK,
     Window::CENTERED_MASK,
-    ChipRunner::W,
-    ChipRunner::H,
-    WindowFlags::OPENGL,
+    w,
+    h,
+    WindowFlags::OPENGL | WindowFlags::RESIZABLE,
   )?;
 
   let renderer: Renderer = window
@@ -65,20 +358,109 @@ fn main() -> Result<(), &'static str> {
   surface.set_color(255, 0, 255);
 
   let texture: Texture = renderer.create_texture_from_surface(surface)?;
-  let audio: AudioBell = AudioBell::new(&token)?;
+  let display: Texture = renderer.create_texture(
+    PixelFormat::ARGB8888,
+    TextureAccess::STREAMING,
+    ChipRunner::TEXTURE.0,
+    ChipRunner::TEXTURE.1,
+  )?;
+  let audio: AudioBell = AudioBell::with_tone(&token, config.tone, config.volume)?;
 
   let context = SDL_Context {
     token: &token,
     renderer: &renderer,
     texture: &texture,
+    display: &display,
     audio: &audio,
   };
 
-  let mut runner: ChipRunner = ChipRunner::new();
-
-  runner.mode(args.mode);
-  runner.load(&args.rom, args.eti)?;
   runner.run(&context);
 
-  Ok(())
+  export_coverage(&runner, args.coverage.as_deref())
+}
+
+fn export_coverage(runner: &ChipRunner, path: Option<&str>) -> Result<(), String> {
+  match path {
+    Some(path) => runner
+      .coverage()
+      .export(path, runner.rom())
+      .map_err(|error| format!("{}: {}", path, error)),
+    None => Ok(()),
+  }
+}
+
+// Returns the config file given with `--config`, or else the default location.
+fn config_file(args: &Args) -> Option<PathBuf> {
+  match args.config.as_ref() {
+    Some(path) => Some(PathBuf::from(path)),
+    None => Config::path(),
+  }
+}
+
+// Reads the config file - empty if there is none at the default location.
+fn load_settings(args: &Args) -> Result<Ini, String> {
+  let path: PathBuf = match config_file(args) {
+    Some(path) if args.config.is_some() || path.exists() => path,
+    _ => return Ok(Ini::new()),
+  };
+
+  let path: String = path.to_string_lossy().into_owned();
+  let settings: Ini = Ini::load(&path).map_err(|error| format!("{}: {}", path, error))?;
+
+  // checked here so errors name the file rather than the ROM
+  Config::new()
+    .apply(&settings, &rom_ids(&settings))
+    .map_err(|error| format!("{}: {}", path, error))?;
+
+  Ok(settings)
+}
+
+// Returns the command line settings applied over the config file to every ROM loaded.
+fn load_overrides(args: &Args) -> Result<Overrides, String> {
+  let keymap: Option<Ini> = match args.keymap.as_ref() {
+    Some(path) => {
+      let ini: Ini = Ini::load(path).map_err(|error| format!("{}: {}", path, error))?;
+
+      for id in rom_ids(&ini) {
+        Keymap::new()
+          .apply_file(&ini, id)
+          .map_err(|error| format!("{}: {}", path, error))?;
+      }
+
+      Some(ini)
+    }
+    None => None,
+  };
+
+  for binding in args.bindings.iter() {
+    Keymap::new()
+      .bind_str(binding)
+      .map_err(|error| format!("--bind: {}: {}", binding, error))?;
+  }
+
+  Ok(Overrides {
+    mode: args.mode,
+    speed: args.speed,
+    quirks: args.quirks.clone(),
+    palette: None,
+    keymap,
+    bindings: args.bindings.clone(),
+    journal: args.journal,
+    fullscreen: args.fullscreen,
+    scale: args.scale,
+    layout: args.layout,
+    roms: args.roms.clone(),
+  })
+}
+
+// Returns the ROMs given their own section in a config or keymap file.
+fn rom_ids(ini: &Ini) -> Vec<&str> {
+  ini
+    .sections()
+    .filter_map(|name| {
+      name
+        .strip_prefix("rom.")
+        .or_else(|| name.strip_prefix("keymap."))
+    })
+    .collect()
 }
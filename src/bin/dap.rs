@@ -0,0 +1,19 @@
+use std::env::args;
+
+use chip::DapServer;
+
+fn main() -> Result<(), &'static str> {
+  let port: Option<String> = args().skip_while(|arg| arg != "--port").nth(1);
+
+  let mut server: DapServer = match port {
+    Some(port) => {
+      let port: u16 = port.parse().map_err(|_| "Invalid Port")?;
+      DapServer::listen(port).map_err(|_| "Failed To Accept Client")?
+    }
+    None => DapServer::stdio(),
+  };
+
+  server.run();
+
+  Ok(())
+}
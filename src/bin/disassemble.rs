@@ -3,41 +3,56 @@ use std::fs::read;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Write;
 
-#[macro_use]
-extern crate chip;
-
+use chip::parse_number;
 use chip::Instruction;
+use chip::Symbols;
+
+const BASE: u16 = 0x200;
+const BASE_ETI: u16 = 0x600;
 
+// Usage: disassemble [--eti | --start-address=ADDR] ROM [SYMBOLS]
 fn main() -> Result<(), Error> {
-  let path: String = args().nth(1).unwrap_or_default();
+  let mut base: u16 = BASE;
+  let mut paths: Vec<String> = Vec::new();
+
+  for arg in args().skip(1) {
+    match arg.strip_prefix("--start-address=") {
+      Some(address) => base = parse_address(address)?,
+      None if arg == "--eti" => base = BASE_ETI,
+      None => paths.push(arg),
+    }
+  }
+
+  let path: String = paths.first().cloned().unwrap_or_default();
   let buffer: Vec<u8> = read(&path)?;
 
+  let symbols: Symbols = match paths.get(1) {
+    Some(path) => Symbols::load(path).map_err(|error| Error::new(ErrorKind::InvalidData, error))?,
+    None => Symbols::new(),
+  };
+
   let file: File = File::create("out.asm")?;
   let mut writer: BufWriter<File> = BufWriter::new(file);
 
-  for opcodes in buffer.chunks(2) {
-    let opcode: u16 = (opcodes[0] as u16) << 8 | opcodes[1] as u16;
+  for (index, opcodes) in buffer.chunks(2).enumerate() {
+    let address: u16 = base.wrapping_add(index as u16 * 2);
+    let opcode: u16 = (opcodes[0] as u16) << 8 | *opcodes.get(1).unwrap_or(&0) as u16;
+
+    if let Some(label) = symbols.label(address) {
+      writeln!(writer, "{}:", label)?;
+    }
 
     if let Some(instruction) = Instruction::find(opcode) {
-      let description: String = instruction
-        .desc
-        .replace("Vx", &format!("V{:X}", x!(opcode)))
-        .replace("Vy", &format!("V{:X}", y!(opcode)))
-        .replace("nibble", &format!("{:X}", n!(opcode)))
-        .replace("byte", &format!("{:02X}", kk!(opcode)))
-        .replace("addr", &format!("{:04X}", nnn!(opcode)));
-
-      writeln!(
-        writer,
-        "{name:<padding$}{description}",
-        name = instruction.name,
-        description = description,
-        padding = if description.is_empty() { 0 } else { 8 },
-      )?;
+      writeln!(writer, "{}", symbols.disassemble(instruction, opcode))?;
     }
   }
 
   Ok(())
 }
+
+fn parse_address(input: &str) -> Result<u16, Error> {
+  parse_number(input).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid Start Address"))
+}
@@ -0,0 +1,191 @@
+use core::cmp::Ordering;
+use std::env::args;
+use std::fs::read_to_string;
+use std::process::exit;
+
+use chip::TraceDiff;
+use chip::TraceMap;
+use chip::TracePair;
+use chip::TraceRecord;
+
+const CONTEXT: usize = 4;
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct Args {
+  pub context: usize,
+  pub left_map: Option<String>,
+  pub right_map: Option<String>,
+  pub paths: Vec<String>,
+}
+
+impl Args {
+  pub fn from_env() -> Self {
+    let mut data: Self = Self {
+      context: CONTEXT,
+      left_map: None,
+      right_map: None,
+      paths: Vec::new(),
+    };
+
+    for arg in args().skip(1) {
+      match arg.as_str() {
+        _ if arg.starts_with("--context=") => data.context = arg[10..].parse().unwrap_or(CONTEXT),
+        _ if arg.starts_with("--left-map=") => data.left_map = Some(arg[11..].to_string()),
+        _ if arg.starts_with("--right-map=") => data.right_map = Some(arg[12..].to_string()),
+        _ => data.paths.push(arg),
+      }
+    }
+
+    data
+  }
+}
+
+// A trace file with the original text of every record kept for reporting.
+struct Trace {
+  path: String,
+  lines: Vec<(usize, String)>,
+  records: Vec<TraceRecord>,
+}
+
+impl Trace {
+  fn load(path: &str, map: Option<&str>) -> Result<Self, String> {
+    let input: String =
+      read_to_string(path).map_err(|_| format!("{}: Invalid Trace File", path))?;
+    let map: Option<TraceMap> = match map {
+      Some(spec) => Some(TraceMap::parse(spec).map_err(|error| format!("{}: {}", spec, error))?),
+      None => None,
+    };
+
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut records: Vec<TraceRecord> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+      if line.trim().is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let record: TraceRecord = match map.as_ref() {
+        Some(map) => map.record(line),
+        None => TraceRecord::parse(line),
+      }
+      .map_err(|error| format!("{}:{}: {}", path, index + 1, error))?;
+
+      lines.push((index + 1, line.to_string()));
+      records.push(record);
+    }
+
+    Ok(Self {
+      path: path.to_string(),
+      lines,
+      records,
+    })
+  }
+
+  fn print(&self, index: usize, marker: char) {
+    let (line, text) = &self.lines[index];
+    println!("{} {}:{}: {}", marker, self.path, line, text);
+  }
+}
+
+fn main() {
+  let args: Args = Args::from_env();
+
+  if args.paths.len() != 2 {
+    eprintln!("Usage: tracediff [--context=N] [--left-map=SPEC] [--right-map=SPEC] LEFT RIGHT");
+    exit(2);
+  }
+
+  let load = |path: &String, map: &Option<String>| {
+    Trace::load(path, map.as_deref()).unwrap_or_else(|error| {
+      eprintln!("{}", error);
+      exit(2);
+    })
+  };
+
+  let left: Trace = load(&args.paths[0], &args.left_map);
+  let right: Trace = load(&args.paths[1], &args.right_map);
+
+  let pairs: Vec<TracePair> = TracePair::align(&left.records, &right.records);
+
+  let print = |pair: TracePair, marker: char| match pair {
+    TracePair::Both(lhs, rhs) => {
+      left.print(lhs, marker);
+      right.print(rhs, marker);
+    }
+    TracePair::Left(lhs) => left.print(lhs, marker),
+    TracePair::Right(rhs) => right.print(rhs, marker),
+  };
+
+  for (index, pair) in pairs.iter().enumerate() {
+    let diff: Vec<TraceDiff> = match *pair {
+      TracePair::Both(lhs, rhs) => {
+        let (lhs, rhs): (&TraceRecord, &TraceRecord) = (&left.records[lhs], &right.records[rhs]);
+        let diff: Vec<TraceDiff> = lhs.diff(rhs);
+
+        if diff.is_empty() {
+          continue;
+        }
+
+        match lhs.cycle.or(rhs.cycle) {
+          Some(cycle) => println!("Traces diverge at record {} (cycle {})", index + 1, cycle),
+          None => println!("Traces diverge at record {}", index + 1),
+        }
+
+        diff
+      }
+      // Only records with a cycle are ever left unpaired
+      TracePair::Left(lhs) => {
+        let cycle: u64 = left.records[lhs].cycle.unwrap_or_default();
+
+        println!(
+          "Traces misaligned at cycle {}: missing from {}",
+          cycle, right.path
+        );
+
+        Vec::new()
+      }
+      TracePair::Right(rhs) => {
+        let cycle: u64 = right.records[rhs].cycle.unwrap_or_default();
+
+        println!(
+          "Traces misaligned at cycle {}: missing from {}",
+          cycle, left.path
+        );
+
+        Vec::new()
+      }
+    };
+
+    println!();
+
+    // Records hold the state *before* execution - the culprit is usually the previous instruction
+    for context in pairs[index.saturating_sub(args.context)..index].iter() {
+      print(*context, ' ');
+    }
+
+    print(*pair, '>');
+
+    if !diff.is_empty() {
+      println!();
+    }
+
+    for entry in diff {
+      println!("  {}", entry);
+    }
+
+    exit(1);
+  }
+
+  match left.records.len().cmp(&right.records.len()) {
+    Ordering::Equal => println!("Traces match ({} records)", left.records.len()),
+    Ordering::Less => {
+      println!("{} ends after {} records", left.path, left.records.len());
+      exit(1);
+    }
+    Ordering::Greater => {
+      println!("{} ends after {} records", right.path, right.records.len());
+      exit(1);
+    }
+  }
+}
@@ -0,0 +1,278 @@
+use std::fs::canonicalize;
+use std::fs::create_dir_all;
+use std::fs::read;
+use std::fs::read_dir;
+use std::fs::read_to_string;
+use std::fs::symlink_metadata;
+use std::fs::write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::database::Program;
+use crate::sha1::sha1;
+
+const RECENT: usize = 8; // recent ROMs kept in the history file
+const DEPTH: usize = 4; // subdirectory levels scanned below the directory
+
+// Extensions of ROM files - files without an extension are listed as well.
+const EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+
+// A ROM listed by the browser.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Rom {
+  pub path: String,
+  pub group: String, // subdirectory of the ROM, or `recent`
+  pub title: String, // database title, or the file name of unknown ROMs
+}
+
+// ROM picker listing the recently loaded ROMs followed by the ROMs under a
+// directory and its subdirectories, ie. `roms/CHIP` and `roms/SCHIP`.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Browser {
+  dir: String,                 // directory listed by the browser
+  history: Option<PathBuf>,    // file the recent ROMs are stored in
+  recent: Vec<String>,         // recently loaded ROMs, most recent first
+  entries: Vec<Rom>,           // ROMs found by the last scan
+  selected: usize,             // index of the highlighted entry
+  error: Option<&'static str>, // why the last ROM picked failed to load
+}
+
+impl Browser {
+  pub fn new(dir: &str) -> Self {
+    Self {
+      dir: dir.to_string(),
+      ..Self::default()
+    }
+  }
+
+  // Reads the recent ROMs from a file - one path per line - and writes them
+  // back there whenever a ROM is loaded.
+  pub fn history(mut self, path: PathBuf) -> Self {
+    self.recent = read_to_string(&path)
+      .unwrap_or_default()
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .take(RECENT)
+      .map(str::to_string)
+      .collect();
+
+    self.history = Some(path);
+    self
+  }
+
+  #[inline]
+  pub fn dir(&self) -> &str {
+    &self.dir
+  }
+
+  #[inline]
+  pub fn recent(&self) -> &[String] {
+    &self.recent
+  }
+
+  #[inline]
+  pub fn entries(&self) -> &[Rom] {
+    &self.entries
+  }
+
+  #[inline]
+  pub fn index(&self) -> usize {
+    self.selected
+  }
+
+  #[inline]
+  pub fn selected(&self) -> Option<&Rom> {
+    self.entries.get(self.selected)
+  }
+
+  #[inline]
+  pub fn error(&self) -> Option<&'static str> {
+    self.error
+  }
+
+  // Lists the recent ROMs that still exist and the ROMs under the directory.
+  pub fn scan(&mut self) {
+    let mut entries: Vec<Rom> = self
+      .recent
+      .iter()
+      .filter_map(|path| entry(Path::new(path), "recent"))
+      .collect();
+
+    scan_dir(Path::new(&self.dir), "", DEPTH, &mut entries);
+
+    self.entries = entries;
+    self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    self.error = None;
+  }
+
+  // Moves the highlight by `amount` entries, stopping at either end.
+  pub fn select(&mut self, amount: i32) {
+    let last: i32 = self.entries.len().saturating_sub(1) as i32;
+
+    self.selected = (self.selected as i32 + amount).clamp(0, last) as usize;
+    self.error = None;
+  }
+
+  // Shows why the highlighted ROM failed to load.
+  pub fn fail(&mut self, error: &'static str) {
+    self.error = Some(error);
+  }
+
+  // Moves a loaded ROM to the top of the recent ROMs and saves them.
+  pub fn visit(&mut self, path: &str) -> Result<(), &'static str> {
+    let path: String = canonicalize(path)
+      .map(|path| path.to_string_lossy().into_owned())
+      .unwrap_or_else(|_| path.to_string());
+
+    self.recent.retain(|recent| *recent != path);
+    self.recent.insert(0, path);
+    self.recent.truncate(RECENT);
+
+    let history: &PathBuf = match self.history.as_ref() {
+      Some(history) => history,
+      None => return Ok(()),
+    };
+
+    if let Some(parent) = history.parent() {
+      create_dir_all(parent).map_err(|_| "Invalid History File")?;
+    }
+
+    write(history, self.recent.join("\n") + "\n").map_err(|_| "Invalid History File")
+  }
+}
+
+// Adds the ROMs in a directory, then those in its subdirectories down to `depth`
+// levels, in name order. Symlinked subdirectories are skipped so a link cycle
+// cannot recurse forever.
+fn scan_dir(dir: &Path, group: &str, depth: usize, entries: &mut Vec<Rom>) {
+  let mut paths: Vec<PathBuf> = match read_dir(dir) {
+    Ok(iter) => iter.filter_map(|item| Some(item.ok()?.path())).collect(),
+    Err(_) => return,
+  };
+
+  paths.sort();
+
+  for path in paths.iter().filter(|path| path.is_file()) {
+    entries.extend(entry(path, group));
+  }
+
+  if depth == 0 {
+    return;
+  }
+
+  for path in paths.iter().filter(|path| is_dir(path)) {
+    let name: String = match path.file_name() {
+      Some(name) => name.to_string_lossy().into_owned(),
+      None => continue,
+    };
+
+    if group.is_empty() {
+      scan_dir(path, &name, depth - 1, entries);
+    } else {
+      scan_dir(path, &format!("{}/{}", group, name), depth - 1, entries);
+    }
+  }
+}
+
+// Returns whether a path is a directory and not a link to one.
+fn is_dir(path: &Path) -> bool {
+  symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+}
+
+// Returns the entry of a ROM file - `None` for hidden files and other documents.
+fn entry(path: &Path, group: &str) -> Option<Rom> {
+  let name: &str = path.file_name()?.to_str()?;
+
+  if name.starts_with('.') {
+    return None;
+  }
+
+  if let Some(extension) = path.extension() {
+    let extension: String = extension.to_string_lossy().to_ascii_lowercase();
+
+    if !EXTENSIONS.contains(&extension.as_str()) {
+      return None;
+    }
+  }
+
+  let rom: Vec<u8> = read(path).ok()?;
+
+  Some(Rom {
+    path: path.to_string_lossy().into_owned(),
+    group: group.to_string(),
+    title: match Program::lookup(&sha1(&rom)) {
+      Some(program) => program.title,
+      None => name.to_string(),
+    },
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_browser() {
+    let mut browser: Browser = Browser::new("roms");
+
+    browser.scan();
+
+    let entries: Vec<Rom> = browser.entries().to_vec();
+    let pong: &Rom = entries.iter().find(|entry| entry.title == "Pong").unwrap();
+
+    assert_eq!(entries[0].group, "CHIP");
+    assert_eq!(entries[0].title, "Blitz");
+    assert_eq!(pong.group, "CHIP");
+    assert!(entries
+      .iter()
+      .any(|entry| entry.group == "SCHIP" && entry.title == "Space Fight"));
+    assert!(entries.iter().all(|entry| !entry.path.ends_with(".txt")));
+
+    browser.select(-1);
+    assert_eq!(browser.index(), 0);
+    browser.select(1000);
+    assert_eq!(browser.index(), entries.len() - 1);
+
+    assert_eq!(browser.visit(&pong.path), Ok(()));
+    assert_eq!(browser.visit("roms/CHIP/BRIX"), Ok(()));
+    assert_eq!(browser.visit(&pong.path), Ok(()));
+    assert_eq!(browser.recent().len(), 2);
+
+    browser.scan();
+
+    assert_eq!(browser.entries()[0].group, "recent");
+    assert_eq!(browser.entries()[0].title, "Pong");
+    assert_eq!(browser.entries()[1].title, "Brix");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_scan_links() {
+    let dir: PathBuf = std::env::temp_dir().join("chip-browser-links");
+    let mut nested: PathBuf = dir.join("a");
+    let mut browser: Browser = Browser::new(&dir.to_string_lossy());
+
+    for name in ["b", "c", "d", "e", "f"] {
+      nested.push(name);
+    }
+
+    create_dir_all(&nested).unwrap();
+    write(dir.join("a/ROM.ch8"), [0x12, 0x00]).unwrap();
+    write(nested.join("DEEP.ch8"), [0x12, 0x00]).unwrap();
+
+    if symlink_metadata(dir.join("a/loop")).is_err() {
+      std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+    }
+
+    browser.scan();
+
+    let groups: Vec<&str> = browser
+      .entries()
+      .iter()
+      .map(|entry| entry.group.as_str())
+      .collect();
+
+    assert_eq!(groups, ["a"]);
+  }
+}
@@ -3,12 +3,19 @@ use core::ops::RangeInclusive;
 use core::ops::RangeTo;
 use core::ops::RangeToInclusive;
 use core::ptr::null_mut;
+use std::collections::BTreeMap;
 use std::fs::read;
 
+use crate::coverage::Access;
+use crate::coverage::Coverage;
+use crate::journal::Journal;
+use crate::profiler::Profiler;
+use crate::trace::Tracer;
+
 const REGISTERS: usize = 0x10;
 const UFLAGS: usize = 0x8;
-const STACK: usize = 0x10;
-const RAM: usize = 0xFFF;
+pub(crate) const STACK: usize = 0x10;
+pub(crate) const RAM: usize = 0xFFF;
 const VF: usize = REGISTERS - 0x1;
 
 const PROG_BASE_VIP: usize = 0x200; // COSMAC VIP
@@ -64,6 +71,81 @@ pub enum Pitch {
   P16 = 16,
 }
 
+bitflags! {
+  // Behaviors that differ between CHIP-8 interpreters.
+  pub struct Quirks: u8 {
+    const SHIFT = 1;     // 8xy6/8xyE shift VX in place instead of VY
+    const MEMORY = 2;    // Fx55/Fx65 leave I unchanged
+    const JUMP = 4;      // Bnnn jumps to xnn + VX instead of nnn + V0
+    const LOGIC = 8;     // 8xy1/8xy2/8xy3 reset VF
+    const OVERFLOW = 16; // Fx1E sets VF when I overflows past 0xFFF
+  }
+}
+
+impl Quirks {
+  pub const NAMES: [(&'static str, Quirks); 5] = [
+    ("shift", Quirks::SHIFT),
+    ("memory", Quirks::MEMORY),
+    ("jump", Quirks::JUMP),
+    ("logic", Quirks::LOGIC),
+    ("overflow", Quirks::OVERFLOW),
+  ];
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::NAMES
+      .iter()
+      .find(|(quirk, _)| quirk.eq_ignore_ascii_case(name.trim()))
+      .map(|(_, quirk)| *quirk)
+  }
+
+  // Parses a comma-separated list of quirk names, ie. `shift, memory`.
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    input
+      .split(',')
+      .filter(|name| !name.trim().is_empty())
+      .try_fold(Self::empty(), |quirks, name| {
+        Self::from_name(name)
+          .map(|quirk| quirks | quirk)
+          .ok_or("Invalid Quirk")
+      })
+  }
+}
+
+impl Mode {
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    match input.trim().to_ascii_lowercase().as_str() {
+      "chip" | "chip8" | "chip-8" => Ok(Self::CHIP),
+      "schip" | "superchip" | "chip48" => Ok(Self::SCHIP),
+      _ => Err("Invalid Mode"),
+    }
+  }
+
+  // Quirks of the original interpreter for this mode.
+  pub fn quirks(self) -> Quirks {
+    match self {
+      Self::CHIP => Quirks::empty(),
+      Self::SCHIP => Quirks::SHIFT | Quirks::MEMORY | Quirks::OVERFLOW,
+    }
+  }
+}
+
+// Most recent DRW instruction that used a sprite address.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Draw {
+  pub pc: u16,  // address of the DRW instruction
+  pub rows: u8, // sprite height - 0 for a 16x16 sprite
+}
+
+// Sprite drawn since the display was last presented, kept for the draw overlay.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DrawCall {
+  pub x: u8,          // left edge in display pixels
+  pub y: u8,          // top edge in display pixels
+  pub rows: u8,       // sprite height
+  pub hits: Vec<u16>, // display indices of pixels erased by the sprite
+  pub erase: bool,    // flag set if every pixel of the sprite was erased
+}
+
 #[repr(C)]
 pub struct Chip8 {
   pub(crate) delay: u8,              // delay timer - decremented at a rate of 60Hz
@@ -74,13 +156,25 @@ pub struct Chip8 {
   pub(crate) reg_v: [u8; REGISTERS], // registers V0-VF - general purpose registers
   pub(crate) reg_u: [u8; UFLAGS],    // registers R0-RF - user-flag registers
   pub(crate) stack: [u16; STACK],    // stack values
+  pub(crate) calls: [u16; STACK],    // entry points of the active subroutines
   pub(crate) display: [u8; Chip8::W * Chip8::H], // display buffer
   pub(crate) memory: [u8; RAM],      // memory buffer
   pub(crate) keys: u16,              // keypad state
   pub(crate) wait: *mut u8,          // pointer to register awaiting keypress
   pub(crate) render: bool,           // flag set if interpreter requires rendering
+  pub(crate) underflow: bool,        // flag set if a RET was executed with an empty stack
+  pub(crate) overflow: bool,         // flag set if a CALL was executed with a full stack
   pub(crate) pitch: Pitch,
   pub(crate) mode: Mode,
+  pub(crate) quirks: Quirks,
+  pub(crate) breakpoints: Vec<u16>,  // addresses that halt an attached debugger
+  pub(crate) cycles: u64,            // number of instructions executed since reset
+  pub(crate) tracer: Option<Tracer>, // per-instruction execution log
+  pub(crate) profiler: Option<Profiler>, // execution statistics
+  pub(crate) coverage: Coverage,     // memory accesses since the ROM was loaded
+  pub(crate) journal: Option<Journal>, // undo journal for stepping backwards
+  pub(crate) draws: BTreeMap<u16, Draw>, // last DRW per sprite address
+  pub(crate) overlay: Option<Vec<DrawCall>>, // sprites drawn in the current frame
 }
 
 impl Chip8 {
@@ -101,19 +195,38 @@ impl Chip8 {
       reg_v: [0; REGISTERS],
       reg_u: [0; UFLAGS],
       stack: [0; STACK],
+      calls: [0; STACK],
       display: [0; Chip8::W * Chip8::H],
       memory: [0; RAM],
       keys: 0,
       wait: null_mut(),
       render: false,
+      underflow: false,
+      overflow: false,
       pitch: Pitch::P8,
       mode: Mode::CHIP,
+      quirks: Mode::CHIP.quirks(),
+      breakpoints: Vec::new(),
+      cycles: 0,
+      tracer: None,
+      profiler: None,
+      coverage: Coverage::new(),
+      journal: None,
+      draws: BTreeMap::new(),
+      overlay: None,
     }
   }
 
+  // Switches the mode and resets the quirks to the defaults of the mode.
   #[inline(always)]
   pub fn mode(&mut self, mode: Mode) {
     self.mode = mode;
+    self.quirks = mode.quirks();
+  }
+
+  #[inline(always)]
+  pub fn quirks(&mut self, quirks: Quirks) {
+    self.quirks = quirks;
   }
 
   #[inline(always)]
@@ -122,36 +235,98 @@ impl Chip8 {
   }
 
   pub fn load(&mut self, path: &str, eti: bool) -> Result<(), &'static str> {
-    self.reset(eti);
+    self.load_at(path, Self::base(eti) as u16)
+  }
 
-    self.write(0, &FONT);
-    self.write(FONT.len(), &XFONT);
+  pub fn load_bytes(&mut self, buffer: &[u8], eti: bool) -> Result<(), &'static str> {
+    self.load_bytes_at(buffer, Self::base(eti) as u16)
+  }
 
+  pub fn load_at(&mut self, path: &str, base: u16) -> Result<(), &'static str> {
     read(path)
-      .map(|buffer| self.write(Self::base(eti), &buffer))
       .map_err(|_| "Invalid ROM")
+      .and_then(|buffer| self.load_bytes_at(&buffer, base))
+  }
+
+  // Loads a program at `base` - execution starts at the first byte of the program.
+  pub fn load_bytes_at(&mut self, buffer: &[u8], base: u16) -> Result<(), &'static str> {
+    if (base as usize) < FONT.len() + XFONT.len() {
+      return Err("Invalid Start Address");
+    }
+
+    if base as usize + buffer.len() >= RAM {
+      return Err("ROM Too Large");
+    }
+
+    self.reset(base);
+
+    self.write(0, &FONT);
+    self.write(FONT.len(), &XFONT);
+    self.write(base as usize, buffer);
+
+    self.coverage.reset(base..base + buffer.len() as u16);
+
+    Ok(())
+  }
+
+  // Seeds the random number generator used by RND.
+  pub fn seed(seed: u32) {
+    unsafe {
+      libc::srand(seed);
+    }
   }
 
   pub fn step(&mut self) -> Option<u16> {
     if self.wait.is_null() {
       let opcode: u16 = self.read(self.pc as usize);
 
+      if let Some(mut tracer) = self.tracer.take() {
+        tracer.record(self, opcode);
+        self.tracer = Some(tracer);
+      }
+
+      let (pc, sp): (u16, u8) = (self.pc, self.sp);
+      let mut journal: Option<Journal> = self.journal.take();
+
+      if let Some(journal) = journal.as_mut() {
+        journal.begin(self, opcode);
+      }
+
+      self.coverage.mark(pc, 2, Access::EXEC);
+
+      if let Some((access, address, length)) = self.access(opcode) {
+        self.coverage.mark(address, length, access);
+      }
+
+      if opcode & 0xF000 == 0xD000 {
+        let draw: Draw = Draw { pc, rows: n!(opcode) };
+        self.draws.insert(self.reg_i, draw);
+      }
+
       self.pc += 2;
+      self.cycles += 1;
 
       self.exec(opcode);
 
+      if let Some(mut profiler) = self.profiler.take() {
+        profiler.record(self, pc, sp, opcode);
+        self.profiler = Some(profiler);
+      }
+
       if self.delay > 0 {
         self.delay -= 1;
       }
 
       if self.sound > 0 {
         self.sound -= 1;
+      }
 
-        if self.sound != 0 {
-          println!("BEEP!");
-        }
+      if let Some(journal) = journal.as_mut() {
+        journal.commit(self);
       }
 
+      self.journal = journal;
+
       return Some(opcode);
     }
 
@@ -285,18 +460,91 @@ impl Chip8 {
     !self.wait.is_null()
   }
 
+  pub fn trace(&mut self, tracer: Option<Tracer>) {
+    self.tracer = tracer;
+  }
+
+  pub fn profile(&mut self, profiler: Option<Profiler>) {
+    self.profiler = profiler;
+  }
+
+  // Notifies the profiler of a rendered frame.
+  pub(crate) fn frame(&mut self) {
+    let wait: Option<u16> = if self.is_waiting() {
+      Some(self.pc.wrapping_sub(2))
+    } else {
+      None
+    };
+
+    if let Some(profiler) = self.profiler.as_mut() {
+      profiler.frame(wait);
+    }
+  }
+
+  // Enables recording of the sprites drawn between frames.
+  pub fn overlay(&mut self, enabled: bool) {
+    self.overlay = if enabled { Some(Vec::new()) } else { None };
+  }
+
+  pub fn journal(&mut self, journal: Option<Journal>) {
+    self.journal = journal;
+  }
+
+  // Reverts the most recently executed instruction - returns false if there is
+  // no journal or it is empty.
+  pub fn step_back(&mut self) -> bool {
+    match self.journal.take() {
+      Some(mut journal) => {
+        let reverted: bool = journal.step_back(self);
+        self.journal = Some(journal);
+        reverted
+      }
+      None => false,
+    }
+  }
+
+  // Replays the most recently reverted instruction and returns its opcode.
+  pub fn step_forward(&mut self) -> Option<u16> {
+    let mut journal: Journal = self.journal.take()?;
+    let opcode: Option<u16> = journal.step_forward(self);
+
+    self.journal = Some(journal);
+
+    opcode
+  }
+
+  pub fn set_breakpoint(&mut self, address: u16) {
+    if !self.is_breakpoint(address) {
+      self.breakpoints.push(address);
+    }
+  }
+
+  pub fn clear_breakpoint(&mut self, address: u16) {
+    self.breakpoints.retain(|&breakpoint| breakpoint != address);
+  }
+
+  pub fn clear_breakpoints(&mut self) {
+    self.breakpoints.clear();
+  }
+
+  #[inline]
+  pub fn is_breakpoint(&self, address: u16) -> bool {
+    self.breakpoints.contains(&address)
+  }
+
   // ===========================================================================
   // Utilities
   // ===========================================================================
 
-  fn reset(&mut self, eti: bool) {
+  fn reset(&mut self, base: u16) {
     blankify!(self.reg_v.iter_mut());
     blankify!(self.reg_u.iter_mut());
     blankify!(self.stack.iter_mut());
+    blankify!(self.calls.iter_mut());
     blankify!(self.display.iter_mut());
     blankify!(self.memory.iter_mut());
 
-    self.pc = Self::base(eti) as u16;
+    self.pc = base;
     self.sp = 0;
 
     self.delay = 0;
@@ -308,6 +556,15 @@ impl Chip8 {
     self.wait = null_mut();
 
     self.render = false;
+    self.underflow = false;
+    self.overflow = false;
+    self.cycles = 0;
+    self.draws.clear();
+
+    if let Some(journal) = self.journal.as_mut() {
+      journal.clear();
+    }
+
     self.pitch = Pitch::P8;
   }
 
@@ -321,8 +578,33 @@ impl Chip8 {
     self.memory[address..address + data.len()].copy_from_slice(data);
   }
 
+  // Returns the width, height and last DRW of the sprite at an address.
+  //
+  // Sprites that were never drawn are assumed to be font glyphs inside the
+  // font area and 8x8 elsewhere.
+  pub(crate) fn sprite(&self, address: u16) -> (u8, u8, Option<Draw>) {
+    match self.draws.get(&address) {
+      Some(draw) if draw.rows == 0 && self.is_mode(Mode::SCHIP) => (16, 16, Some(*draw)),
+      Some(draw) => (8, draw.rows.max(1), Some(*draw)),
+      None if (address as usize) < FONT.len() => (8, 5, None),
+      None if (address as usize) < FONT.len() + XFONT.len() => (8, 10, None),
+      None => (8, 8, None),
+    }
+  }
+
+  // Returns the memory range accessed through I by an opcode about to execute.
+  pub(crate) fn access(&self, opcode: u16) -> Option<(Access, u16, u16)> {
+    match opcode & 0xF0FF {
+      0xF033 => Some((Access::WRITE, self.reg_i, 3)),
+      0xF055 => Some((Access::WRITE, self.reg_i, x!(opcode) as u16 + 1)),
+      0xF065 => Some((Access::READ, self.reg_i, x!(opcode) as u16 + 1)),
+      _ if opcode & 0xF000 == 0xD000 => Some((Access::READ, self.reg_i, n!(opcode) as u16)),
+      _ => None,
+    }
+  }
+
   #[inline(always)]
-  fn base(eti: bool) -> usize {
+  pub(crate) fn base(eti: bool) -> usize {
     if eti {
       PROG_BASE_ETI
     } else {
@@ -341,13 +623,20 @@ impl Chip8 {
   }
 
   // Returns from a subroutine.
+  //
+  // Returning with an empty stack is flagged and otherwise ignored.
   fn ret(&mut self) { // 00EE - RET
+    if self.sp == 0 {
+      self.underflow = true;
+      return;
+    }
+
     self.sp -= 1;
     self.pc = self.stack[self.sp as usize];
   }
 
   fn sys_addr(&mut self, nnn: u16) { // 0nnn - SYS addr
-    println!("TODO: Jump to a machine code routine at nnn({}).", nnn);
+    eprintln!("TODO: Jump to a machine code routine at nnn({}).", nnn);
   }
 
   // Jumps to address NNN.
@@ -356,8 +645,16 @@ impl Chip8 {
   }
 
   // Calls subroutine at NNN.
+  //
+  // Calling with a full stack is flagged and otherwise ignored.
   fn call_addr(&mut self, nnn: u16) { // 2nnn - CALL addr
+    if self.sp as usize == STACK {
+      self.overflow = true;
+      return;
+    }
+
     self.stack[self.sp as usize] = self.pc;
+    self.calls[self.sp as usize] = nnn;
     self.sp += 1;
     self.pc = nnn;
   }
@@ -408,16 +705,26 @@ impl Chip8 {
   // Sets VX to VX or VY.
   fn or_vx_vy(&mut self, x: u8, y: u8) { // 8xy1 - OR Vx, Vy
     self.reg_v[x as usize] |= self.reg_v[y as usize];
+    self.reset_vf();
   }
 
   // Sets VX to VX and VY.
   fn and_vx_vy(&mut self, x: u8, y: u8) { // 8xy2 - AND Vx, Vy
     self.reg_v[x as usize] &= self.reg_v[y as usize];
+    self.reset_vf();
   }
 
   // Sets VX to VX xor VY.
   fn xor_vx_vy(&mut self, x: u8, y: u8) { // 8xy3 - XOR Vx, Vy
     self.reg_v[x as usize] ^= self.reg_v[y as usize];
+    self.reset_vf();
+  }
+
+  // The logic instructions of the COSMAC VIP clobber VF.
+  fn reset_vf(&mut self) {
+    if self.quirks.contains(Quirks::LOGIC) {
+      self.reg_v[VF] = 0x0;
+    }
   }
 
   // Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
@@ -464,7 +771,7 @@ impl Chip8 {
 
   // Stores the least significant bit of VX in VF and then shifts VX to the right by 1.
   fn shr_vx_vy(&mut self, x: u8, y: u8) { // 8xy6 - SHR Vx {, Vy}
-    let source: u8 = if self.is_mode(Mode::SCHIP) {
+    let source: u8 = if self.quirks.contains(Quirks::SHIFT) {
       self.reg_v[x as usize]
     } else {
       self.reg_v[y as usize]
@@ -476,7 +783,7 @@ impl Chip8 {
 
   // Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
   fn shl_vx_vy(&mut self, x: u8, y: u8) { // 8xyE - SHL Vx {, Vy}
-    let source: u8 = if self.is_mode(Mode::SCHIP) {
+    let source: u8 = if self.quirks.contains(Quirks::SHIFT) {
       self.reg_v[x as usize]
     } else {
       self.reg_v[y as usize]
@@ -493,7 +800,13 @@ impl Chip8 {
 
   // Jumps to the address NNN + V0.
   fn jp_v0_addr(&mut self, nnn: u16) { // Bnnn - JP V0, addr
-    self.pc = self.reg_v[0] as u16 + nnn;
+    let x: usize = if self.quirks.contains(Quirks::JUMP) {
+      (nnn >> 0x8) as usize
+    } else {
+      0x0
+    };
+
+    self.pc = self.reg_v[x] as u16 + nnn;
   }
 
   // Sets VX to the result of a bitwise and operation on a random number and NN.
@@ -504,6 +817,8 @@ impl Chip8 {
   fn drw_vx_vy_nibble(&mut self, x: u8, y: u8, mut n: u8) { // Dxyn - DRW Vx, Vy, nibble
     let x: usize = self.reg_v[x as usize] as usize;
     let y: usize = self.reg_v[y as usize] as usize;
+    let mut hits: Vec<u16> = Vec::new();
+    let mut pixels: usize = 0;
 
     self.reg_v[VF] = 0x0;
 
@@ -517,14 +832,26 @@ impl Chip8 {
           if let Some(pixel) = self.display.get_mut(index) {
             if *pixel == 0x1 {
               self.reg_v[VF] = 0x1;
+              hits.push(index as u16);
             }
 
             *pixel ^= 0x1;
+            pixels += 1;
           }
         }
       }
     }
 
+    if let Some(overlay) = self.overlay.as_mut() {
+      overlay.push(DrawCall {
+        x: x as u8,
+        y: y as u8,
+        rows: n,
+        erase: !hits.is_empty() && hits.len() == pixels,
+        hits,
+      });
+    }
+
     self.render = true;
   }
 
@@ -573,7 +900,7 @@ impl Chip8 {
   // and to 0 when there isn't. This is an undocumented feature of the
   // CHIP-8 and used by the Spacefight 2091! game.
   fn add_i_vx(&mut self, x: u8) { // Fx1E - ADD I, Vx
-    if self.is_mode(Mode::SCHIP) {
+    if self.quirks.contains(Quirks::OVERFLOW) {
       let num: u16 = self.reg_i.wrapping_add(self.reg_v[x as usize] as u16);
 
       self.reg_v[VF] = if num > 0xFFF { 0x1 } else { 0x0 };
@@ -607,7 +934,7 @@ impl Chip8 {
 
     self.memory[output].copy_from_slice(&self.reg_v[source]);
 
-    if self.is_mode(Mode::CHIP) {
+    if !self.quirks.contains(Quirks::MEMORY) {
       self.reg_i += x as u16 + 0x1;
     }
   }
@@ -620,7 +947,7 @@ impl Chip8 {
 
     self.reg_v[output].copy_from_slice(&self.memory[source]);
 
-    if self.is_mode(Mode::CHIP) {
+    if !self.quirks.contains(Quirks::MEMORY) {
       self.reg_i += x as u16 + 0x1;
     }
   }
@@ -978,4 +1305,103 @@ mod tests {
   fn test_scd_nibble() {
     unimplemented!()
   }
+
+  #[test]
+  fn test_or_vx_vy_logic() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_v[VF] = 0x1;
+    chip8.exec(0x8011);
+    assert_eq!(chip8.reg_v[VF], 0x1);
+
+    chip8.quirks(Quirks::LOGIC);
+    chip8.exec(0x8011);
+    assert_eq!(chip8.reg_v[VF], 0x0);
+  }
+
+  #[test]
+  fn test_jp_v0_addr_jump() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_v[0x0] = 0x2;
+    chip8.reg_v[0x3] = 0x4;
+    chip8.exec(0xB300);
+    assert_eq!(chip8.pc, 0x302);
+
+    chip8.quirks(Quirks::JUMP);
+    chip8.exec(0xB300);
+    assert_eq!(chip8.pc, 0x304);
+  }
+
+  #[test]
+  fn test_shr_vx_vy_shift() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_v[0x1] = 0x5;
+    chip8.exec(0x8016);
+    assert_eq!((chip8.reg_v[0x0], chip8.reg_v[VF]), (0x2, 0x1));
+
+    chip8.quirks(Quirks::SHIFT);
+    chip8.reg_v[0x0] = 0x8;
+    chip8.exec(0x8016);
+    assert_eq!((chip8.reg_v[0x0], chip8.reg_v[VF]), (0x4, 0x0));
+  }
+
+  #[test]
+  fn test_shl_vx_vy_shift() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_v[0x1] = 0x81;
+    chip8.exec(0x801E);
+    assert_eq!((chip8.reg_v[0x0], chip8.reg_v[VF]), (0x2, 0x1));
+
+    chip8.quirks(Quirks::SHIFT);
+    chip8.reg_v[0x0] = 0x3;
+    chip8.exec(0x801E);
+    assert_eq!((chip8.reg_v[0x0], chip8.reg_v[VF]), (0x6, 0x0));
+  }
+
+  #[test]
+  fn test_add_i_vx_overflow() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_i = 0xFFF;
+    chip8.reg_v[0x0] = 0x1;
+    chip8.exec(0xF01E);
+    assert_eq!((chip8.reg_i, chip8.reg_v[VF]), (0x1000, 0x0));
+
+    chip8.quirks(Quirks::OVERFLOW);
+    chip8.reg_i = 0xFFF;
+    chip8.exec(0xF01E);
+    assert_eq!((chip8.reg_i, chip8.reg_v[VF]), (0x1000, 0x1));
+  }
+
+  #[test]
+  fn test_ld_i_vx_memory() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_i = 0x300;
+    chip8.reg_v[0x1] = 0x2A;
+    chip8.exec(0xF155);
+    assert_eq!((chip8.reg_i, chip8.memory[0x301]), (0x302, 0x2A));
+
+    chip8.quirks(Quirks::MEMORY);
+    chip8.exec(0xF155);
+    assert_eq!((chip8.reg_i, chip8.memory[0x303]), (0x302, 0x2A));
+  }
+
+  #[test]
+  fn test_ld_vx_i_memory() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.reg_i = 0x300;
+    chip8.memory[0x301] = 0x2A;
+    chip8.exec(0xF165);
+    assert_eq!((chip8.reg_i, chip8.reg_v[0x1]), (0x302, 0x2A));
+
+    chip8.quirks(Quirks::MEMORY);
+    chip8.memory[0x303] = 0x15;
+    chip8.exec(0xF165);
+    assert_eq!((chip8.reg_i, chip8.reg_v[0x1]), (0x302, 0x15));
+  }
 }
@@ -0,0 +1,409 @@
+use std::env::var_os;
+use std::path::PathBuf;
+
+use crate::afterimage::Persistence;
+use crate::chip8::Mode;
+use crate::chip8::Quirks;
+use crate::database::detect;
+use crate::database::Program;
+use crate::ini::Ini;
+use crate::keymap::Action;
+use crate::keymap::Keymap;
+use crate::palette::parse_color;
+use crate::palette::Palette;
+use crate::scaler::Filter;
+use crate::sha1::sha1;
+
+const SPEED: u64 = 700;
+const SCALE: u32 = 10;
+const MAX_SCALE: u32 = 32; // keeps play layout windows within 4K screens
+const TONE: u32 = 480;
+const VOLUME: u8 = 25;
+const DECAY: u8 = 40;
+const ROMS: &str = "roms";
+
+// Windows the runner can show.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Layout {
+  Debug, // display, registers, history and memory panels
+  Play,  // display only
+}
+
+impl Layout {
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    match input.trim() {
+      "debug" => Ok(Self::Debug),
+      "play" => Ok(Self::Play),
+      _ => Err("Invalid Layout"),
+    }
+  }
+
+  pub fn next(self) -> Self {
+    match self {
+      Self::Debug => Self::Play,
+      Self::Play => Self::Debug,
+    }
+  }
+}
+
+// Settings of the `chip` binary.
+//
+// The config file is an INI file read from `--config=PATH`, or else from
+// `$XDG_CONFIG_HOME/chip/chip.ini` (`~/.config/chip/chip.ini`). Entries before
+// the first section apply to every ROM:
+//
+//   mode = schip           # chip or schip
+//   speed = 700            # instructions per second
+//   quirks = shift, memory # replaces the quirks of the mode (shift, memory, jump, logic, overflow)
+//   theme = classic        # classic, green, amber, lcd, octo or gameboy
+//   foreground = #FFFFFF   # color of lit pixels - replaces the color of the theme
+//   background = #000000   # color of unlit pixels
+//   border = #808080       # color of the frame around the display
+//   foreground2 = #AAAAAA  # color of pixels lit in the second plane only
+//   blend = #555555        # color of pixels lit in both planes
+//   persistence = off      # hides flicker - off, or, phosphor or blend
+//   decay = 40             # percent of phosphor brightness lost per frame
+//   filter = nearest       # nearest, scale2x, scale3x, hqx or scanlines
+//   scale = 10             # size of a low-res pixel in the play layout (1 to 32)
+//   layout = debug         # debug or play
+//   fullscreen = off       # on or off
+//   audio = on             # on or off
+//   tone = 480             # beep frequency in Hz
+//   volume = 25            # beep amplitude from 0 to 127
+//   roms = roms            # directory listed by the ROM browser
+//   journal = off          # record instructions so they can be stepped back
+//
+// The `[keymap]` section holds key bindings (see `Keymap`). A `[rom.ID]` and a
+// `[keymap.ID]` section override the settings for a single ROM, where ID is the
+// SHA-1 of the ROM or its file name without extension.
+//
+// Settings missing from the file come from the ROM database (see `Program`),
+// or for unknown ROMs from a scan of the ROM for SCHIP instructions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+  pub mode: Option<Mode>, // mode selected before loading if unset
+  pub speed: u64,
+  pub quirks: Option<Quirks>, // quirks of the mode if unset
+  pub palette: Palette,
+  pub persistence: Persistence,
+  pub decay: u8,
+  pub filter: Filter,
+  pub scale: u32,
+  pub layout: Layout,
+  pub fullscreen: bool,
+  pub audio: bool,
+  pub tone: u32,
+  pub volume: u8,
+  pub roms: String,
+  pub journal: bool, // flag set if executed instructions are recorded for step back
+  pub keymap: Keymap,
+}
+
+impl Config {
+  pub fn new() -> Self {
+    Self {
+      mode: None,
+      speed: SPEED,
+      quirks: None,
+      palette: Palette::new(),
+      persistence: Persistence::Off,
+      decay: DECAY,
+      filter: Filter::Nearest,
+      scale: SCALE,
+      layout: Layout::Debug,
+      fullscreen: false,
+      audio: true,
+      tone: TONE,
+      volume: VOLUME,
+      roms: ROMS.to_string(),
+      journal: false,
+      keymap: Keymap::new(),
+    }
+  }
+
+  // Returns the settings of a ROM loaded at `base` before any config file is applied.
+  pub fn for_rom(rom: &[u8], base: u16) -> Self {
+    let mut config: Self = Self::new();
+
+    match Program::lookup(&sha1(rom)) {
+      Some(program) => config.apply_program(&program),
+      None => config.mode = Some(detect(rom, base).mode()),
+    }
+
+    config
+  }
+
+  pub fn apply_program(&mut self, program: &Program) {
+    self.mode = Some(program.platform.mode());
+    self.quirks = program.quirks;
+
+    if let Some(speed) = program.speed {
+      self.speed = speed;
+    }
+
+    for (input, key) in program.bindings() {
+      self.keymap.set(input, Action::Key(key));
+    }
+  }
+
+  // Returns the default config file location.
+  pub fn path() -> Option<PathBuf> {
+    let base: PathBuf = match var_os("XDG_CONFIG_HOME") {
+      Some(path) if !path.is_empty() => PathBuf::from(path),
+      _ => PathBuf::from(var_os("HOME")?).join(".config"),
+    };
+
+    Some(base.join("chip").join("chip.ini"))
+  }
+
+  // Applies the global settings and the overrides of the ROM known by any of `ids`.
+  pub fn apply(&mut self, ini: &Ini, ids: &[&str]) -> Result<(), &'static str> {
+    self.apply_section(ini, "")?;
+    self.keymap.apply(ini, "keymap")?;
+
+    for id in ids {
+      self.apply_section(ini, &format!("rom.{}", id))?;
+      self.keymap.apply(ini, &format!("keymap.{}", id))?;
+    }
+
+    Ok(())
+  }
+
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), &'static str> {
+    match key {
+      "mode" => self.mode = Some(Mode::parse(value)?),
+      "speed" => {
+        self.speed = value
+          .parse()
+          .ok()
+          .filter(|speed| *speed > 0)
+          .ok_or("Invalid Speed")?
+      }
+      "quirks" => self.quirks = Some(Quirks::parse(value)?),
+      "theme" => self.palette = Palette::theme(value)?,
+      "foreground" => self.palette.foreground = parse_color(value)?,
+      "background" => self.palette.background = parse_color(value)?,
+      "border" => self.palette.border = parse_color(value)?,
+      "foreground2" => self.palette.foreground2 = parse_color(value)?,
+      "blend" => self.palette.blend = parse_color(value)?,
+      "persistence" => self.persistence = Persistence::parse(value)?,
+      "decay" => {
+        self.decay = value
+          .parse()
+          .ok()
+          .filter(|decay| *decay <= 100)
+          .ok_or("Invalid Decay")?
+      }
+      "filter" => self.filter = Filter::parse(value)?,
+      "scale" => self.scale = parse_scale(value).ok_or("Invalid Scale")?,
+      "layout" => self.layout = Layout::parse(value)?,
+      "fullscreen" => self.fullscreen = parse_bool(value).ok_or("Invalid Fullscreen")?,
+      "audio" => self.audio = parse_bool(value).ok_or("Invalid Audio")?,
+      "tone" => self.tone = value.parse().map_err(|_| "Invalid Tone")?,
+      "volume" => self.volume = value.parse().map_err(|_| "Invalid Volume")?,
+      "roms" => self.roms = value.trim().to_string(),
+      "journal" => self.journal = parse_bool(value).ok_or("Invalid Journal")?,
+      _ => return Err("Invalid Config Key"),
+    }
+
+    Ok(())
+  }
+
+  fn apply_section(&mut self, ini: &Ini, section: &str) -> Result<(), &'static str> {
+    for (key, value) in ini.section(section) {
+      self.set(key, value)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Settings given on the command line or picked while running, applied over the
+// config file to every ROM loaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+  pub mode: Option<Mode>,
+  pub speed: Option<u64>,
+  pub quirks: Vec<(Quirks, bool)>,
+  pub palette: Option<Palette>,
+  pub keymap: Option<Ini>,   // keymap file
+  pub bindings: Vec<String>, // single bindings, ie. `1=up`
+  pub journal: bool,         // records instructions even if the config file does not
+  pub fullscreen: bool,      // goes fullscreen even if the config file does not
+  pub scale: Option<u32>,
+  pub layout: Option<Layout>,
+  pub roms: Option<String>,
+}
+
+impl Overrides {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn apply(&self, config: &mut Config, name: &str) -> Result<(), &'static str> {
+    if let Some(ini) = self.keymap.as_ref() {
+      config.keymap.apply_file(ini, name)?;
+    }
+
+    for binding in self.bindings.iter() {
+      config.keymap.bind_str(binding)?;
+    }
+
+    if self.mode.is_some() {
+      config.mode = self.mode;
+    }
+
+    if let Some(speed) = self.speed {
+      config.speed = speed;
+    }
+
+    if let Some(palette) = self.palette {
+      config.palette = palette;
+    }
+
+    if self.journal {
+      config.journal = true;
+    }
+
+    if self.fullscreen {
+      config.fullscreen = true;
+    }
+
+    if let Some(scale) = self.scale {
+      config.scale = scale;
+    }
+
+    if let Some(layout) = self.layout {
+      config.layout = layout;
+    }
+
+    if let Some(roms) = self.roms.as_ref() {
+      config.roms = roms.clone();
+    }
+
+    if !self.quirks.is_empty() {
+      let mode: Mode = config.mode.unwrap_or(Mode::CHIP);
+      let mut quirks: Quirks = config.quirks.unwrap_or_else(|| mode.quirks());
+
+      for (quirk, value) in self.quirks.iter() {
+        quirks.set(*quirk, *value);
+      }
+
+      config.quirks = Some(quirks);
+    }
+
+    Ok(())
+  }
+}
+
+// Parses a play layout scale, rejecting scales a window cannot show.
+pub fn parse_scale(input: &str) -> Option<u32> {
+  input
+    .trim()
+    .parse::<u32>()
+    .ok()
+    .filter(|scale| (1..=MAX_SCALE).contains(scale))
+}
+
+pub fn parse_bool(input: &str) -> Option<bool> {
+  match input.trim() {
+    "on" | "true" | "yes" | "1" => Some(true),
+    "off" | "false" | "no" | "0" => Some(false),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keymap::Action;
+  use crate::keymap::Input;
+  use crate::sdl2::SDL_Scancode;
+
+  #[test]
+  fn test_rom_section_overrides_global_settings() {
+    let mut config: Config = Config::new();
+    let ini: Ini = Ini::parse(
+      "speed = 900\nquirks = shift, jump\nbackground = #102030\n[rom.PONG]\nspeed = 1200\naudio = off\n[keymap.PONG]\n1 = up\n[rom.BRIX]\nspeed = 10\n",
+    )
+    .unwrap();
+
+    config.apply(&ini, &["PONG", "0123"]).unwrap();
+
+    assert_eq!(config.speed, 1200);
+    assert_eq!(config.quirks, Some(Quirks::SHIFT | Quirks::JUMP));
+    assert_eq!(config.palette.background, (0x10, 0x20, 0x30));
+    assert!(!config.audio);
+    assert_eq!(
+      config
+        .keymap
+        .get(Input::Key(SDL_Scancode::SDL_SCANCODE_UP as u32)),
+      Some(Action::Key(0x1))
+    );
+  }
+
+  #[test]
+  fn test_invalid_values() {
+    let mut config: Config = Config::new();
+
+    assert_eq!(config.set("volume", "loud"), Err("Invalid Volume"));
+    assert_eq!(config.set("speed", "0"), Err("Invalid Speed"));
+    assert_eq!(config.set("color", "#FFFFFF"), Err("Invalid Config Key"));
+  }
+
+  #[test]
+  fn test_scale_range() {
+    let mut config: Config = Config::new();
+
+    assert_eq!(config.set("scale", "32").map(|_| config.scale), Ok(32));
+    assert_eq!(config.set("scale", "1").map(|_| config.scale), Ok(1));
+    assert_eq!(config.set("scale", "500"), Err("Invalid Scale"));
+    assert_eq!(config.set("scale", "0"), Err("Invalid Scale"));
+    assert_eq!(config.scale, 1);
+  }
+
+  #[test]
+  fn test_overrides() {
+    let mut config: Config = Config::new();
+    let overrides: Overrides = Overrides {
+      mode: Some(Mode::SCHIP),
+      speed: Some(2000),
+      quirks: vec![(Quirks::LOGIC, false)],
+      palette: Some(Palette::theme("amber").unwrap()),
+      keymap: None,
+      bindings: vec!["1=up".to_string()],
+      journal: true,
+      fullscreen: false,
+      scale: Some(4),
+      layout: Some(Layout::Play),
+      roms: Some("games".to_string()),
+    };
+
+    config.set("speed", "900").unwrap();
+    config.set("fullscreen", "on").unwrap();
+    overrides.apply(&mut config, "PONG").unwrap();
+
+    assert_eq!(config.mode, Some(Mode::SCHIP));
+    assert_eq!(config.speed, 2000);
+    assert_eq!(config.quirks, Some(Mode::SCHIP.quirks() - Quirks::LOGIC));
+    assert_eq!(config.palette, Palette::theme("amber").unwrap());
+    assert_eq!(
+      config
+        .keymap
+        .get(Input::Key(SDL_Scancode::SDL_SCANCODE_UP as u32)),
+      Some(Action::Key(0x1))
+    );
+    assert!(config.journal);
+    assert_eq!(config.scale, 4);
+    assert_eq!(config.layout, Layout::Play);
+    assert!(config.fullscreen);
+    assert_eq!(config.roms, "games");
+  }
+}
@@ -0,0 +1,194 @@
+use core::ops::Range;
+use std::fs::write;
+
+use crate::json::Json;
+
+const ADDRESSES: usize = 0x1000;
+
+bitflags! {
+  pub struct Access: u8 {
+    const EXEC = 1;
+    const READ = 2;
+    const WRITE = 4;
+  }
+}
+
+// Records how every byte of memory was accessed since the ROM was loaded.
+pub struct Coverage {
+  map: Vec<Access>,
+  rom: Range<u16>,
+}
+
+impl Coverage {
+  pub fn new() -> Self {
+    Self {
+      map: vec![Access::empty(); ADDRESSES],
+      rom: 0..0,
+    }
+  }
+
+  pub(crate) fn reset(&mut self, rom: Range<u16>) {
+    for access in self.map.iter_mut() {
+      *access = Access::empty();
+    }
+
+    self.rom = rom;
+  }
+
+  pub(crate) fn mark(&mut self, address: u16, length: u16, access: Access) {
+    for offset in 0..length {
+      self.map[address.wrapping_add(offset) as usize % ADDRESSES] |= access;
+    }
+  }
+
+  #[inline]
+  pub fn get(&self, address: u16) -> Access {
+    self.map[address as usize % ADDRESSES]
+  }
+
+  #[inline]
+  pub fn rom(&self) -> Range<u16> {
+    self.rom.clone()
+  }
+
+  // Counts the ROM bytes with any of the given access flags.
+  pub fn count(&self, access: Access) -> usize {
+    self
+      .rom()
+      .filter(|address| self.get(*address).intersects(access))
+      .count()
+  }
+
+  // Serializes every accessed address, ie. `{"0x0200": "x", "0x02EA": "rw"}`.
+  pub fn to_json(&self) -> Json {
+    let addresses: Vec<(String, Json)> = (0..ADDRESSES as u16)
+      .filter(|address| !self.get(*address).is_empty())
+      .map(|address| {
+        (
+          format!("{:#06X}", address),
+          Json::from(flags(self.get(address))),
+        )
+      })
+      .collect();
+
+    Json::object(vec![
+      (
+        "rom",
+        Json::object(vec![
+          ("start", Json::from(self.rom.start)),
+          ("end", Json::from(self.rom.end)),
+        ]),
+      ),
+      (
+        "summary",
+        Json::object(vec![
+          ("bytes", Json::from(self.rom.len())),
+          ("executed", Json::from(self.count(Access::EXEC))),
+          ("read", Json::from(self.count(Access::READ))),
+          ("written", Json::from(self.count(Access::WRITE))),
+          (
+            "untouched",
+            Json::from(self.rom.len() - self.count(Access::all())),
+          ),
+        ]),
+      ),
+      ("addresses", Json::Object(addresses)),
+    ])
+  }
+
+  // Serializes the ROM in lcov format with one line per instruction word.
+  //
+  // Line N refers to the word at `start + (N - 1) * 2`, matching the
+  // disassembly shown by the debug adapter. Words that were only accessed as
+  // data are omitted so sprites don't show up as uncovered code.
+  pub fn to_lcov(&self, source: &str) -> String {
+    let mut output: String = format!("TN:\nSF:{}\n", source);
+    let mut found: usize = 0;
+    let mut hit: usize = 0;
+
+    for (line, address) in self.rom().step_by(2).enumerate() {
+      let access: Access = self.get(address) | self.get(address + 1);
+
+      if !access.contains(Access::EXEC) && access.intersects(Access::READ | Access::WRITE) {
+        continue;
+      }
+
+      let count: usize = access.contains(Access::EXEC) as usize;
+
+      output.push_str(&format!("DA:{},{}\n", line + 1, count));
+
+      found += 1;
+      hit += count;
+    }
+
+    output.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", found, hit));
+    output
+  }
+
+  // Writes a JSON report for `*.json` paths and an lcov report otherwise.
+  pub fn export(&self, path: &str, source: &str) -> Result<(), &'static str> {
+    let output: String = if path.ends_with(".json") {
+      self.to_json().to_string()
+    } else {
+      self.to_lcov(source)
+    };
+
+    write(path, output).map_err(|_| "Invalid Coverage File")
+  }
+}
+
+impl Default for Coverage {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn flags(access: Access) -> String {
+  [
+    (Access::EXEC, 'x'),
+    (Access::READ, 'r'),
+    (Access::WRITE, 'w'),
+  ]
+  .iter()
+  .filter(|(flag, _)| access.contains(*flag))
+  .map(|(_, char)| *char)
+  .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::chip8::Chip8;
+
+  #[test]
+  fn test_coverage() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    // LD I, 0x208 / LD V1, [I] / JP 0x204 / data
+    chip8
+      .load_bytes(
+        &[0xA2, 0x08, 0xF1, 0x65, 0x12, 0x04, 0x00, 0x00, 0xAA, 0xBB],
+        false,
+      )
+      .unwrap();
+
+    for _ in 0..3 {
+      chip8.step();
+    }
+
+    let coverage: &Coverage = &chip8.coverage;
+
+    assert_eq!(coverage.get(0x202), Access::EXEC);
+    assert_eq!(coverage.get(0x209), Access::READ);
+    assert!(coverage.get(0x206).is_empty());
+    assert_eq!(coverage.count(Access::EXEC), 6);
+    assert_eq!(
+      coverage.to_lcov("rom.ch8"),
+      "TN:\nSF:rom.ch8\nDA:1,1\nDA:2,1\nDA:3,1\nDA:4,0\nLF:4\nLH:3\nend_of_record\n"
+    );
+    assert_eq!(
+      coverage.to_json().path(&["addresses", "0x0208"]),
+      Some(&Json::from("r"))
+    );
+  }
+}
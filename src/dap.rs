@@ -0,0 +1,1160 @@
+use core::convert::TryFrom;
+use core::str::from_utf8;
+use std::fs::read;
+use std::io::stdin;
+use std::io::stdout;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::TryRecvError;
+use std::thread::sleep;
+use std::thread::spawn;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::chip8::Chip8;
+use crate::chip8::Mode;
+use crate::instruction::Instruction;
+use crate::journal;
+use crate::journal::Journal;
+use crate::journal::Target;
+use crate::json::Json;
+use crate::number::parse_number;
+use crate::symbols::Symbols;
+
+const THREAD: i64 = 1;
+const SOURCE: i64 = 1; // reference of the virtual disassembly listing
+
+const SCOPE_REGISTERS: i64 = 1;
+const SCOPE_STACK: i64 = 2;
+
+const SPEED: u64 = 700; // default execution speed
+const STEP_BUDGET: u64 = 0x4000; // instructions executed per loop while stepping
+const INSTRUCTIONS: i64 = 0x800; // most instructions returned by a disassemble request
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+enum Step {
+  Into,     // stop after a single instruction
+  Over(u8), // stop once the stack is back at the given depth
+  Out(u8),  // stop once the stack is below the given depth
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+enum State {
+  Idle,    // waiting for a launch request
+  Paused,  // stopped by a breakpoint, step or pause request
+  Running, // executing at the configured speed
+  Stepping(Step),
+  Terminated,
+}
+
+// Debug Adapter Protocol server running a headless `Chip8`.
+//
+// The ROM is presented as a virtual disassembly source where line N holds the
+// instruction at `base + (N - 1) * 2`, so editors can place breakpoints by line.
+// Stepping back requires the `journal` launch argument.
+pub struct DapServer {
+  input: Receiver<Json>,
+  output: Box<dyn Write>,
+  seq: i64,
+  state: State,
+  events: Vec<Json>, // events queued until the current response is sent
+  dispatching: bool, // flag set while a request is being handled
+  base: u16,         // load address of the ROM
+  size: u16,         // size of the ROM in bytes
+  speed: u64,        // execution speed while running
+  entry: bool,       // flag set if execution stops on entry
+  resume: bool,      // flag set if the breakpoint at PC is skipped when resuming
+  time: Instant,     // execution timestamp
+  cycles: u64,       // cycles executed since `time`
+  line_breakpoints: Vec<u16>,
+  instruction_breakpoints: Vec<u16>,
+  symbols: Symbols,
+  chip8: Chip8,
+}
+
+impl DapServer {
+  pub fn stdio() -> Self {
+    Self::new(reader(stdin()), Box::new(stdout()))
+  }
+
+  // Waits for a single client on a localhost port.
+  pub fn listen(port: u16) -> IoResult<Self> {
+    let listener: TcpListener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let (stream, _) = listener.accept()?;
+
+    Ok(Self::new(reader(stream.try_clone()?), Box::new(stream)))
+  }
+
+  fn new(input: Receiver<Json>, output: Box<dyn Write>) -> Self {
+    let chip8: Chip8 = Chip8::new();
+
+    Self {
+      input,
+      output,
+      seq: 0,
+      state: State::Idle,
+      events: Vec::new(),
+      dispatching: false,
+      base: 0,
+      size: 0,
+      speed: SPEED,
+      entry: false,
+      resume: false,
+      time: Instant::now(),
+      cycles: 0,
+      line_breakpoints: Vec::new(),
+      instruction_breakpoints: Vec::new(),
+      symbols: Symbols::new(),
+      chip8,
+    }
+  }
+
+  pub fn run(&mut self) {
+    while self.state != State::Terminated {
+      loop {
+        match self.input.try_recv() {
+          Ok(message) => self.dispatch(&message),
+          Err(TryRecvError::Empty) => break,
+          Err(TryRecvError::Disconnected) => {
+            self.state = State::Terminated;
+            break;
+          }
+        }
+      }
+
+      match self.state {
+        State::Running => {
+          let count: u64 = self.time.elapsed().as_nanos() as u64 * self.speed / 1_000_000_000;
+
+          if count > self.cycles {
+            self.execute(count - self.cycles);
+            self.cycles = count;
+          }
+
+          sleep(Duration::from_millis(1));
+        }
+        State::Stepping(_) => self.execute(STEP_BUDGET),
+        _ => sleep(Duration::from_millis(1)),
+      }
+    }
+  }
+
+  fn execute(&mut self, budget: u64) {
+    for _ in 0..budget {
+      if !self.resume && self.chip8.is_breakpoint(self.chip8.pc) {
+        self.stop("breakpoint");
+        return;
+      }
+
+      self.resume = false;
+      self.chip8.step();
+
+      let stop: bool = match self.state {
+        State::Stepping(Step::Into) => true,
+        State::Stepping(Step::Over(depth)) => self.chip8.sp <= depth,
+        State::Stepping(Step::Out(depth)) => self.chip8.sp < depth,
+        _ => false,
+      };
+
+      if stop {
+        self.stop("step");
+        return;
+      }
+
+      if self.chip8.is_waiting() {
+        return;
+      }
+    }
+  }
+
+  fn stop(&mut self, reason: &str) {
+    self.state = State::Paused;
+    self.event(
+      "stopped",
+      Json::object(vec![
+        ("reason", Json::from(reason)),
+        ("threadId", Json::from(THREAD)),
+        ("allThreadsStopped", Json::from(true)),
+      ]),
+    );
+  }
+
+  fn resume(&mut self, state: State) {
+    self.state = state;
+    self.resume = true;
+    self.time = Instant::now();
+    self.cycles = 0;
+  }
+
+  // ===========================================================================
+  // Messages
+  // ===========================================================================
+
+  fn dispatch(&mut self, message: &Json) {
+    if message.get("type").and_then(Json::as_str) != Some("request") {
+      return;
+    }
+
+    let seq: i64 = message.get("seq").and_then(Json::as_i64).unwrap_or(0);
+    let command: &str = message.get("command").and_then(Json::as_str).unwrap_or("");
+    let arguments: Json = message
+      .get("arguments")
+      .cloned()
+      .unwrap_or_else(|| Json::Object(Vec::new()));
+
+    self.dispatching = true;
+
+    let result: Result<Json, &'static str> = match command {
+      "initialize" => Ok(capabilities()),
+      "launch" => self.launch(&arguments),
+      "configurationDone" => self.configuration_done(),
+      "setBreakpoints" => self.set_breakpoints(&arguments),
+      "setInstructionBreakpoints" => self.set_instruction_breakpoints(&arguments),
+      "setExceptionBreakpoints" => Ok(Json::object(vec![("breakpoints", Json::Array(Vec::new()))])),
+      "threads" => Ok(threads()),
+      "stackTrace" => Ok(self.stack_trace()),
+      "scopes" => Ok(scopes()),
+      "variables" => self.variables(&arguments),
+      "setVariable" => self.set_variable(&arguments),
+      "source" => Ok(self.source()),
+      "readMemory" => self.read_memory(&arguments),
+      "writeMemory" => self.write_memory(&arguments),
+      "disassemble" => self.disassemble(&arguments),
+      "continue" => self.continue_(),
+      "next" => self.step(Step::Over(self.chip8.sp)),
+      "stepIn" => self.step(Step::Into),
+      "stepOut" => self.step(Step::Out(self.chip8.sp)),
+      "stepBack" => self.step_back(false),
+      "reverseContinue" => self.step_back(true),
+      "evaluate" => self.evaluate(&arguments),
+      "pause" => self.pause(),
+      "disconnect" | "terminate" => {
+        self.state = State::Terminated;
+        Ok(Json::Null)
+      }
+      _ => Err("Unsupported Request"),
+    };
+
+    self.respond(seq, command, result);
+    self.dispatching = false;
+
+    for event in self.events.split_off(0) {
+      self.send(event);
+    }
+  }
+
+  fn respond(&mut self, request: i64, command: &str, result: Result<Json, &'static str>) {
+    let mut response: Json = Json::object(vec![
+      ("type", Json::from("response")),
+      ("request_seq", Json::from(request)),
+      ("success", Json::from(result.is_ok())),
+      ("command", Json::from(command)),
+    ]);
+
+    match result {
+      Ok(Json::Null) => {}
+      Ok(body) => response.set("body", body),
+      Err(message) => response.set("message", Json::from(message)),
+    }
+
+    self.send(response);
+  }
+
+  fn event(&mut self, event: &str, body: Json) {
+    let mut message: Json = Json::object(vec![
+      ("type", Json::from("event")),
+      ("event", Json::from(event)),
+    ]);
+
+    if body != Json::Null {
+      message.set("body", body);
+    }
+
+    self.events.push(message);
+
+    // Events raised by execution are not tied to a response
+    if !self.dispatching {
+      for event in self.events.split_off(0) {
+        self.send(event);
+      }
+    }
+  }
+
+  fn send(&mut self, mut message: Json) {
+    self.seq += 1;
+    message.set("seq", Json::from(self.seq));
+
+    let body: String = message.to_string();
+
+    // A closed output ends the session on the next read
+    write!(
+      self.output,
+      "Content-Length: {}\r\n\r\n{}",
+      body.len(),
+      body
+    )
+    .and_then(|_| self.output.flush())
+    .ok();
+  }
+
+  // ===========================================================================
+  // Requests
+  // ===========================================================================
+
+  fn launch(&mut self, arguments: &Json) -> Result<Json, &'static str> {
+    let program: &str = arguments
+      .get("program")
+      .and_then(Json::as_str)
+      .ok_or("Missing Program")?;
+
+    let eti: bool = arguments
+      .get("eti")
+      .and_then(Json::as_bool)
+      .unwrap_or(false);
+    let rom: Vec<u8> = read(program).map_err(|_| "Invalid ROM")?;
+
+    self
+      .chip8
+      .mode(match arguments.get("mode").and_then(Json::as_str) {
+        Some("schip") => Mode::SCHIP,
+        _ => Mode::CHIP,
+      });
+
+    self.chip8.load_bytes(&rom, eti)?;
+    self.chip8.journal(
+      arguments
+        .get("journal")
+        .and_then(Json::as_bool)
+        .unwrap_or(false)
+        .then(Journal::new),
+    );
+
+    if let Some(path) = arguments.get("symbols").and_then(Json::as_str) {
+      self.symbols = Symbols::load(path)?;
+    }
+
+    self.base = self.chip8.pc;
+    self.size = rom.len() as u16;
+    self.entry = arguments
+      .get("stopOnEntry")
+      .and_then(Json::as_bool)
+      .unwrap_or(false);
+    self.speed = arguments
+      .get("speed")
+      .and_then(Json::as_i64)
+      .map_or(SPEED, |speed| speed.max(1) as u64);
+
+    self.state = State::Paused;
+    self.refresh_breakpoints();
+    self.event("initialized", Json::Null);
+
+    Ok(Json::Null)
+  }
+
+  fn configuration_done(&mut self) -> Result<Json, &'static str> {
+    if self.state == State::Idle {
+      return Err("Not Launched");
+    }
+
+    if self.entry {
+      self.stop("entry");
+    } else {
+      self.resume(State::Running);
+    }
+
+    Ok(Json::Null)
+  }
+
+  fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, &'static str> {
+    let lines: Vec<i64> = arguments
+      .get("breakpoints")
+      .and_then(Json::as_array)
+      .unwrap_or_default()
+      .iter()
+      .filter_map(|breakpoint| breakpoint.get("line").and_then(Json::as_i64))
+      .collect();
+
+    self.line_breakpoints.clear();
+
+    let breakpoints: Vec<Json> = lines
+      .into_iter()
+      .map(|line| {
+        let address: Option<u16> = self.line_address(line);
+
+        if let Some(address) = address {
+          self.line_breakpoints.push(address);
+        }
+
+        let mut breakpoint: Json = Json::object(vec![
+          ("verified", Json::from(address.is_some())),
+          ("line", Json::from(line)),
+        ]);
+
+        if let Some(address) = address {
+          breakpoint.set("instructionReference", Json::from(reference(address)));
+        }
+
+        breakpoint
+      })
+      .collect();
+
+    self.refresh_breakpoints();
+
+    Ok(Json::object(vec![(
+      "breakpoints",
+      Json::Array(breakpoints),
+    )]))
+  }
+
+  fn set_instruction_breakpoints(&mut self, arguments: &Json) -> Result<Json, &'static str> {
+    let mut breakpoints: Vec<Json> = Vec::new();
+
+    self.instruction_breakpoints.clear();
+
+    for breakpoint in arguments
+      .get("breakpoints")
+      .and_then(Json::as_array)
+      .unwrap_or_default()
+    {
+      let address: Option<u16> = breakpoint
+        .get("instructionReference")
+        .and_then(Json::as_str)
+        .and_then(parse_number)
+        .map(|address| {
+          let offset: i64 = breakpoint.get("offset").and_then(Json::as_i64).unwrap_or(0);
+          (address as i64 + offset) as u16
+        });
+
+      if let Some(address) = address {
+        self.instruction_breakpoints.push(address);
+      }
+
+      breakpoints.push(Json::object(vec![(
+        "verified",
+        Json::from(address.is_some()),
+      )]));
+    }
+
+    self.refresh_breakpoints();
+
+    Ok(Json::object(vec![(
+      "breakpoints",
+      Json::Array(breakpoints),
+    )]))
+  }
+
+  fn stack_trace(&self) -> Json {
+    let mut frames: Vec<Json> = vec![self.frame(0, self.chip8.pc)];
+
+    // Every return address on the stack points just past its CALL
+    for (depth, address) in self.chip8.stack[..self.chip8.sp as usize]
+      .iter()
+      .rev()
+      .enumerate()
+    {
+      frames.push(self.frame(depth as i64 + 1, address.wrapping_sub(2)));
+    }
+
+    Json::object(vec![
+      ("totalFrames", Json::from(frames.len())),
+      ("stackFrames", Json::Array(frames)),
+    ])
+  }
+
+  fn frame(&self, id: i64, address: u16) -> Json {
+    let mut frame: Json = Json::object(vec![
+      ("id", Json::from(id)),
+      ("name", Json::from(self.symbols.locate(address))),
+      ("line", Json::from(self.address_line(address).unwrap_or(0))),
+      ("column", Json::from(1u8)),
+      (
+        "instructionPointerReference",
+        Json::from(reference(address)),
+      ),
+    ]);
+
+    if self.address_line(address).is_some() {
+      frame.set("source", source());
+    }
+
+    frame
+  }
+
+  fn variables(&self, arguments: &Json) -> Result<Json, &'static str> {
+    let scope: i64 = arguments
+      .get("variablesReference")
+      .and_then(Json::as_i64)
+      .ok_or("Invalid Arguments")?;
+
+    let variables: Vec<Json> = match scope {
+      SCOPE_REGISTERS => {
+        let mut variables: Vec<Json> = self
+          .chip8
+          .reg_v
+          .iter()
+          .enumerate()
+          .map(|(index, value)| variable(&format!("V{:X}", index), format!("{:#04X}", value), None))
+          .collect();
+
+        variables.push(variable(
+          "I",
+          format!("{:#06X}", self.chip8.reg_i),
+          Some(self.chip8.reg_i),
+        ));
+        variables.push(variable(
+          "PC",
+          format!("{:#06X}", self.chip8.pc),
+          Some(self.chip8.pc),
+        ));
+        variables.push(variable("SP", format!("{:#04X}", self.chip8.sp), None));
+        variables.push(variable("DT", format!("{:#04X}", self.chip8.delay), None));
+        variables.push(variable("ST", format!("{:#04X}", self.chip8.sound), None));
+        variables
+      }
+      SCOPE_STACK => self.chip8.stack[..self.chip8.sp as usize]
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+          variable(
+            &format!("S{:X}", index),
+            format!("{:#06X}", value),
+            Some(*value),
+          )
+        })
+        .collect(),
+      _ => Vec::new(),
+    };
+
+    Ok(Json::object(vec![("variables", Json::Array(variables))]))
+  }
+
+  fn set_variable(&mut self, arguments: &Json) -> Result<Json, &'static str> {
+    let name: &str = arguments
+      .get("name")
+      .and_then(Json::as_str)
+      .ok_or("Invalid Arguments")?;
+    let value: u16 = arguments
+      .get("value")
+      .and_then(Json::as_str)
+      .and_then(parse_number)
+      .ok_or("Invalid Value")?;
+
+    let target: Target = match Target::parse(name) {
+      Some(target @ Target::V(_))
+      | Some(target @ Target::I)
+      | Some(target @ Target::Pc)
+      | Some(target @ Target::Sp)
+      | Some(target @ Target::Dt)
+      | Some(target @ Target::St) => target,
+      _ => return Err("Read-Only Variable"),
+    };
+
+    journal::edit(&mut self.chip8, target, value)?;
+
+    Ok(Json::object(vec![(
+      "value",
+      Json::from(format!("{:#04X}", value)),
+    )]))
+  }
+
+  fn source(&self) -> Json {
+    let content: String = (0..self.size)
+      .step_by(2)
+      .map(|offset| {
+        let address: u16 = self.base + offset;
+        let opcode: u16 = self.opcode(address);
+
+        format!(
+          "{:04X}  {:04X}  {}\n",
+          address,
+          opcode,
+          self.disassemble_opcode(opcode)
+        )
+      })
+      .collect();
+
+    Json::object(vec![
+      ("content", Json::from(content)),
+      ("mimeType", Json::from("text/x-chip8-asm")),
+    ])
+  }
+
+  fn read_memory(&self, arguments: &Json) -> Result<Json, &'static str> {
+    let address: usize = memory_address(arguments)?;
+    let count: usize = arguments
+      .get("count")
+      .and_then(Json::as_i64)
+      .unwrap_or(0)
+      .max(0) as usize;
+    let end: usize = (address + count).min(self.chip8.memory.len());
+    let data: &[u8] = self.chip8.memory.get(address..end).unwrap_or_default();
+
+    Ok(Json::object(vec![
+      ("address", Json::from(reference(address as u16))),
+      ("data", Json::from(encode_base64(data))),
+      ("unreadableBytes", Json::from(count - data.len())),
+    ]))
+  }
+
+  fn write_memory(&mut self, arguments: &Json) -> Result<Json, &'static str> {
+    let address: usize = memory_address(arguments)?;
+    let data: Vec<u8> = arguments
+      .get("data")
+      .and_then(Json::as_str)
+      .and_then(decode_base64)
+      .ok_or("Invalid Data")?;
+
+    journal::edit_memory(&mut self.chip8, address, &data)?;
+
+    Ok(Json::object(vec![("bytesWritten", Json::from(data.len()))]))
+  }
+
+  fn disassemble(&self, arguments: &Json) -> Result<Json, &'static str> {
+    let address: i64 = memory_address(arguments)? as i64;
+    let offset: i64 = arguments
+      .get("instructionOffset")
+      .and_then(Json::as_i64)
+      .unwrap_or(0);
+    let count: i64 = arguments
+      .get("instructionCount")
+      .and_then(Json::as_i64)
+      .ok_or("Invalid Arguments")?
+      .min(INSTRUCTIONS);
+
+    let instructions: Vec<Json> = (0..count)
+      .map(|index| {
+        let address: i64 = address + (offset + index) * 2;
+
+        if address < 0 || address as usize + 1 >= self.chip8.memory.len() {
+          return Json::object(vec![
+            ("address", Json::from(format!("{:#06X}", address.max(0)))),
+            ("instruction", Json::from("??")),
+            ("presentationHint", Json::from("invalid")),
+          ]);
+        }
+
+        let opcode: u16 = self.opcode(address as u16);
+        let mut instruction: Json = Json::object(vec![
+          ("address", Json::from(reference(address as u16))),
+          ("instructionBytes", Json::from(format!("{:04X}", opcode))),
+          ("instruction", Json::from(self.disassemble_opcode(opcode))),
+        ]);
+
+        if let Some(line) = self.address_line(address as u16) {
+          instruction.set("location", source());
+          instruction.set("line", Json::from(line));
+        }
+
+        instruction
+      })
+      .collect();
+
+    Ok(Json::object(vec![(
+      "instructions",
+      Json::Array(instructions),
+    )]))
+  }
+
+  fn continue_(&mut self) -> Result<Json, &'static str> {
+    self.resume(State::Running);
+
+    Ok(Json::object(vec![(
+      "allThreadsContinued",
+      Json::from(true),
+    )]))
+  }
+
+  fn step(&mut self, step: Step) -> Result<Json, &'static str> {
+    self.resume(State::Stepping(step));
+
+    Ok(Json::Null)
+  }
+
+  // Reverts instructions from the undo journal, one or until a breakpoint.
+  fn step_back(&mut self, until_breakpoint: bool) -> Result<Json, &'static str> {
+    if self.chip8.journal.is_none() {
+      return Err("Journal Disabled");
+    }
+
+    if !self.chip8.step_back() {
+      return Err("No Instruction To Step Back To");
+    }
+
+    while until_breakpoint && !self.chip8.is_breakpoint(self.chip8.pc) {
+      if !self.chip8.step_back() {
+        break;
+      }
+    }
+
+    if self.chip8.is_breakpoint(self.chip8.pc) {
+      self.stop("breakpoint");
+    } else {
+      self.stop("step");
+    }
+
+    Ok(Json::Null)
+  }
+
+  // Answers undo journal queries such as `[0x3F0]` or `V3 == 0`.
+  fn evaluate(&self, arguments: &Json) -> Result<Json, &'static str> {
+    let expression: &str = arguments
+      .get("expression")
+      .and_then(Json::as_str)
+      .ok_or("Missing Expression")?;
+
+    let journal: &Journal = self.chip8.journal.as_ref().ok_or("Journal Disabled")?;
+
+    Ok(Json::object(vec![
+      ("result", Json::from(journal.answer(expression, &self.symbols))),
+      ("variablesReference", Json::from(0)),
+    ]))
+  }
+
+  fn pause(&mut self) -> Result<Json, &'static str> {
+    self.stop("pause");
+
+    Ok(Json::Null)
+  }
+
+  // ===========================================================================
+  // Utilities
+  // ===========================================================================
+
+  fn refresh_breakpoints(&mut self) {
+    self.chip8.clear_breakpoints();
+
+    for &address in self
+      .line_breakpoints
+      .iter()
+      .chain(&self.instruction_breakpoints)
+    {
+      self.chip8.set_breakpoint(address);
+    }
+  }
+
+  fn line_address(&self, line: i64) -> Option<u16> {
+    let offset: i64 = (line - 1) * 2;
+
+    if offset >= 0 && offset < self.size as i64 {
+      Some(self.base + offset as u16)
+    } else {
+      None
+    }
+  }
+
+  fn address_line(&self, address: u16) -> Option<i64> {
+    if address >= self.base && address < self.base + self.size {
+      Some((address - self.base) as i64 / 2 + 1)
+    } else {
+      None
+    }
+  }
+
+  fn disassemble_opcode(&self, opcode: u16) -> String {
+    match Instruction::find(opcode) {
+      Some(instruction) => self.symbols.disassemble(instruction, opcode),
+      None => format!("DW      {:04X}", opcode),
+    }
+  }
+
+  fn opcode(&self, address: u16) -> u16 {
+    let address: usize = address as usize;
+
+    (self.chip8.memory[address] as u16) << 8 | self.chip8.memory[address + 1] as u16
+  }
+}
+
+fn reader<R: Read + Send + 'static>(input: R) -> Receiver<Json> {
+  let (sender, receiver) = channel();
+
+  spawn(move || {
+    let mut input: BufReader<R> = BufReader::new(input);
+
+    while let Some(message) = read_message(&mut input) {
+      if sender.send(message).is_err() {
+        break;
+      }
+    }
+  });
+
+  receiver
+}
+
+// Reads one `Content-Length` framed message - malformed bodies become `Null`.
+fn read_message<R: BufRead>(input: &mut R) -> Option<Json> {
+  let mut length: Option<usize> = None;
+  let mut line: String = String::new();
+
+  loop {
+    line.clear();
+
+    if input.read_line(&mut line).ok()? == 0 {
+      return None;
+    }
+
+    match line.trim_end() {
+      "" if length.is_some() => break,
+      "" => continue,
+      header => {
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+          length = value.trim().parse().ok();
+        }
+      }
+    }
+  }
+
+  let mut body: Vec<u8> = vec![0; length?];
+
+  input.read_exact(&mut body).ok()?;
+
+  Some(
+    from_utf8(&body)
+      .ok()
+      .and_then(|body| Json::parse(body).ok())
+      .unwrap_or(Json::Null),
+  )
+}
+
+fn capabilities() -> Json {
+  Json::object(vec![
+    ("supportsConfigurationDoneRequest", Json::from(true)),
+    ("supportsSetVariable", Json::from(true)),
+    ("supportsReadMemoryRequest", Json::from(true)),
+    ("supportsWriteMemoryRequest", Json::from(true)),
+    ("supportsDisassembleRequest", Json::from(true)),
+    ("supportsInstructionBreakpoints", Json::from(true)),
+    ("supportsTerminateRequest", Json::from(true)),
+    ("supportsStepBack", Json::from(true)),
+  ])
+}
+
+fn threads() -> Json {
+  Json::object(vec![(
+    "threads",
+    Json::Array(vec![Json::object(vec![
+      ("id", Json::from(THREAD)),
+      ("name", Json::from("CHIP-8")),
+    ])]),
+  )])
+}
+
+fn scopes() -> Json {
+  Json::object(vec![(
+    "scopes",
+    Json::Array(vec![
+      Json::object(vec![
+        ("name", Json::from("Registers")),
+        ("presentationHint", Json::from("registers")),
+        ("variablesReference", Json::from(SCOPE_REGISTERS)),
+        ("expensive", Json::from(false)),
+      ]),
+      Json::object(vec![
+        ("name", Json::from("Stack")),
+        ("variablesReference", Json::from(SCOPE_STACK)),
+        ("expensive", Json::from(false)),
+      ]),
+    ]),
+  )])
+}
+
+fn source() -> Json {
+  Json::object(vec![
+    ("name", Json::from("ROM Disassembly")),
+    ("sourceReference", Json::from(SOURCE)),
+  ])
+}
+
+fn variable(name: &str, value: String, memory: Option<u16>) -> Json {
+  let mut variable: Json = Json::object(vec![
+    ("name", Json::from(name)),
+    ("value", Json::from(value)),
+    ("variablesReference", Json::from(0u8)),
+  ]);
+
+  if let Some(address) = memory {
+    variable.set("memoryReference", Json::from(reference(address)));
+  }
+
+  variable
+}
+
+fn memory_address(arguments: &Json) -> Result<usize, &'static str> {
+  let address: u16 = arguments
+    .get("memoryReference")
+    .and_then(Json::as_str)
+    .and_then(parse_number)
+    .ok_or("Invalid Memory Reference")?;
+
+  let offset: i64 = arguments.get("offset").and_then(Json::as_i64).unwrap_or(0);
+
+  usize::try_from(address as i64 + offset).map_err(|_| "Invalid Memory Reference")
+}
+
+#[inline]
+fn reference(address: u16) -> String {
+  format!("{:#06X}", address)
+}
+
+fn encode_base64(data: &[u8]) -> String {
+  let mut output: String = String::new();
+
+  for chunk in data.chunks(3) {
+    let bytes: [u8; 3] = [
+      chunk[0],
+      *chunk.get(1).unwrap_or(&0),
+      *chunk.get(2).unwrap_or(&0),
+    ];
+    let word: u32 = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+    for index in 0..4 {
+      if index <= chunk.len() {
+        output.push(BASE64[(word >> (18 - index * 6)) as usize & 0x3F] as char);
+      } else {
+        output.push('=');
+      }
+    }
+  }
+
+  output
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+  let mut output: Vec<u8> = Vec::new();
+  let mut word: u32 = 0;
+  let mut bits: u32 = 0;
+
+  for byte in data.bytes().filter(|&byte| byte != b'=') {
+    word = word << 6 | BASE64.iter().position(|&char| char == byte)? as u32;
+    bits += 6;
+
+    if bits >= 8 {
+      bits -= 8;
+      output.push((word >> bits) as u8);
+    }
+  }
+
+  Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+  use std::env::temp_dir;
+  use std::fs::write;
+  use std::path::PathBuf;
+  use std::rc::Rc;
+
+  // Output shared between a server and the session driving it.
+  struct Output(Rc<RefCell<Vec<u8>>>);
+
+  impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+      self.0.borrow_mut().extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+      Ok(())
+    }
+  }
+
+  // Sends a request and returns the messages the server wrote back.
+  fn request(server: &mut DapServer, output: &Rc<RefCell<Vec<u8>>>, text: &str) -> Vec<Json> {
+    server.dispatch(&Json::parse(text).unwrap());
+    receive(output)
+  }
+
+  // Takes the messages written since the last call.
+  fn receive(output: &Rc<RefCell<Vec<u8>>>) -> Vec<Json> {
+    let data: Vec<u8> = output.borrow_mut().split_off(0);
+    let mut input: &[u8] = &data;
+    let mut messages: Vec<Json> = Vec::new();
+
+    while let Some(message) = read_message(&mut input) {
+      messages.push(message);
+    }
+
+    messages
+  }
+
+  fn success(messages: &[Json]) -> Option<bool> {
+    messages
+      .iter()
+      .find(|message| message.get("type").and_then(Json::as_str) == Some("response"))?
+      .get("success")
+      .and_then(Json::as_bool)
+  }
+
+  #[test]
+  fn test_base64() {
+    for data in [&b""[..], b"f", b"fo", b"foo", b"foob", b"\x00\xFF\x10\x80"] {
+      assert_eq!(decode_base64(&encode_base64(data)).as_deref(), Some(data));
+    }
+
+    assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    assert_eq!(encode_base64(b"fooba"), "Zm9vYmE=");
+    assert_eq!(decode_base64("Zm9vYg=="), Some(b"foob".to_vec()));
+    assert_eq!(decode_base64("Zm9v!"), None);
+  }
+
+  #[test]
+  fn test_read_message() {
+    let mut input: &[u8] =
+      b"Content-Length: 10\r\n\r\n{\"seq\": 1}\r\nContent-Length: 3\r\n\r\n{x}";
+
+    assert_eq!(
+      read_message(&mut input).and_then(|json| json.get("seq").and_then(Json::as_i64)),
+      Some(1)
+    );
+    assert_eq!(read_message(&mut input), Some(Json::Null));
+    assert_eq!(read_message(&mut input), None);
+  }
+
+  #[test]
+  fn test_session() {
+    let path: PathBuf = temp_dir().join("chip-dap-session.ch8");
+    let output: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let (_sender, receiver) = channel();
+    let mut server: DapServer = DapServer::new(receiver, Box::new(Output(output.clone())));
+
+    // LD V0, 0x2A - LD V1, 0x05 - JP 0x204
+    write(&path, [0x60, 0x2A, 0x61, 0x05, 0x12, 0x04]).unwrap();
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 1, "command": "initialize", "arguments": {}}"#,
+    );
+    assert_eq!(success(&messages), Some(true));
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      &format!(
+        r#"{{"type": "request", "seq": 2, "command": "launch", "arguments": {{"program": "{}"}}}}"#,
+        path.to_string_lossy()
+      ),
+    );
+    assert_eq!(success(&messages), Some(true));
+    assert!(messages
+      .iter()
+      .any(|message| message.get("event").and_then(Json::as_str) == Some("initialized")));
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 3, "command": "setBreakpoints",
+          "arguments": {"source": {"sourceReference": 1}, "breakpoints": [{"line": 3}]}}"#,
+    );
+    assert_eq!(success(&messages), Some(true));
+    assert_eq!(
+      messages[0]
+        .path(&["body", "breakpoints"])
+        .and_then(Json::as_array)
+        .unwrap()[0]
+        .get("verified")
+        .and_then(Json::as_bool),
+      Some(true)
+    );
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 4, "command": "configurationDone"}"#,
+    );
+    assert_eq!(success(&messages), Some(true));
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 5, "command": "continue", "arguments": {"threadId": 1}}"#,
+    );
+    assert_eq!(success(&messages), Some(true));
+
+    server.execute(0x10);
+    assert_eq!(server.state, State::Paused);
+    assert_eq!(server.chip8.pc, 0x204);
+
+    let messages: Vec<Json> = receive(&output);
+    assert_eq!(
+      messages[0].path(&["body", "reason"]).and_then(Json::as_str),
+      Some("breakpoint")
+    );
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 6, "command": "variables", "arguments": {"variablesReference": 1}}"#,
+    );
+    let variables: &[Json] = messages[0]
+      .path(&["body", "variables"])
+      .and_then(Json::as_array)
+      .unwrap();
+    assert_eq!(
+      variables[0].get("value").and_then(Json::as_str),
+      Some("0x2A")
+    );
+    assert_eq!(
+      variables[1].get("value").and_then(Json::as_str),
+      Some("0x05")
+    );
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 7, "command": "setVariable",
+          "arguments": {"variablesReference": 1, "name": "V1", "value": "0x10"}}"#,
+    );
+    assert_eq!(success(&messages), Some(true));
+    assert_eq!(server.chip8.reg_v[1], 0x10);
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 8, "command": "setVariable",
+          "arguments": {"variablesReference": 1, "name": "PC", "value": "0xFFF"}}"#,
+    );
+    assert_eq!(success(&messages), Some(false));
+    assert_eq!(server.chip8.pc, 0x204);
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 9, "command": "setVariable",
+          "arguments": {"variablesReference": 1, "name": "SP", "value": "0x10"}}"#,
+    );
+    assert_eq!(success(&messages), Some(false));
+    assert_eq!(server.chip8.sp, 0);
+
+    let messages: Vec<Json> = request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 10, "command": "disassemble",
+          "arguments": {"memoryReference": "0x0", "instructionCount": 1000000}}"#,
+    );
+    assert_eq!(
+      messages[0]
+        .path(&["body", "instructions"])
+        .and_then(Json::as_array)
+        .map(<[Json]>::len),
+      Some(INSTRUCTIONS as usize)
+    );
+
+    request(
+      &mut server,
+      &output,
+      r#"{"type": "request", "seq": 11, "command": "disconnect"}"#,
+    );
+    assert_eq!(server.state, State::Terminated);
+  }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::chip8::Mode;
+use crate::chip8::Quirks;
+use crate::json::Json;
+use crate::keymap::Input;
+
+// Known ROMs in the format of the community chip-8-database `programs.json`.
+static DATABASE: &str = include_str!("database.json");
+
+// Programs of the database by ROM SHA-1, parsed on the first lookup.
+static PROGRAMS: OnceLock<BTreeMap<String, Program>> = OnceLock::new();
+
+// Quirk names of `quirkyPlatforms` entries.
+const QUIRKS: [(&str, Quirks); 4] = [
+  ("shift", Quirks::SHIFT),
+  ("memoryLeaveIUnchanged", Quirks::MEMORY),
+  ("jump", Quirks::JUMP),
+  ("logic", Quirks::LOGIC),
+];
+
+// Controller inputs of the directions and buttons named by `keys` entries.
+const KEYS: [(&str, &str); 6] = [
+  ("up", "pad-up, pad-ly-"),
+  ("down", "pad-down, pad-ly+"),
+  ("left", "pad-left, pad-lx-"),
+  ("right", "pad-right, pad-lx+"),
+  ("a", "pad-a"),
+  ("b", "pad-b"),
+];
+
+// Frames per second - the database `tickrate` counts instructions per frame.
+const FPS: u64 = 60;
+
+// Interpreter a ROM was written for.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Platform {
+  Chip8,     // COSMAC VIP CHIP-8 and its close variants
+  SuperChip, // CHIP-48 and SUPER-CHIP 1.0/1.1
+  XOChip,    // XO-CHIP
+}
+
+impl Platform {
+  pub fn parse(name: &str) -> Option<Self> {
+    match name {
+      "originalChip8" | "hybridVIP" | "modernChip8" => Some(Self::Chip8),
+      "chip48" | "superchip1" | "superchip" => Some(Self::SuperChip),
+      "xochip" => Some(Self::XOChip),
+      _ => None,
+    }
+  }
+
+  // Closest supported mode - XO-CHIP runs as SCHIP, its SCHIP subset works.
+  pub fn mode(self) -> Mode {
+    match self {
+      Self::Chip8 => Mode::CHIP,
+      Self::SuperChip | Self::XOChip => Mode::SCHIP,
+    }
+  }
+}
+
+// Database entry of a single ROM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+  pub title: String,
+  pub authors: Vec<String>,
+  pub platform: Platform,
+  pub quirks: Option<Quirks>,  // quirks of the mode if unset
+  pub speed: Option<u64>,      // instructions per second
+  pub keys: Vec<(String, u8)>, // CHIP-8 keys by purpose, ie. `up` or `a`
+}
+
+impl Program {
+  // Finds the ROM with the given SHA-1 (see `sha1`).
+  pub fn lookup(hash: &str) -> Option<Self> {
+    PROGRAMS.get_or_init(programs).get(hash).cloned()
+  }
+
+  // Host inputs that press the CHIP-8 keys of the ROM.
+  pub fn bindings(&self) -> Vec<(Input, u8)> {
+    let mut bindings: Vec<(Input, u8)> = Vec::new();
+
+    for (name, key) in self.keys.iter() {
+      if let Some((_, inputs)) = KEYS.iter().find(|(purpose, _)| purpose == name) {
+        for input in inputs
+          .split(',')
+          .filter_map(|input| Input::parse(input).ok())
+        {
+          bindings.push((input, *key));
+        }
+      }
+    }
+
+    bindings
+  }
+
+  fn parse(program: &Json, rom: &Json) -> Option<Self> {
+    let (name, platform): (&str, Platform) = rom
+      .get("platforms")?
+      .as_array()?
+      .iter()
+      .filter_map(Json::as_str)
+      .find_map(|name| Some((name, Platform::parse(name)?)))?;
+
+    let quirks: Option<Quirks> = rom
+      .path(&["quirkyPlatforms", name])
+      .and_then(Json::as_object)
+      .map(|fields| {
+        fields
+          .iter()
+          .fold(platform.mode().quirks(), |mut quirks, (name, value)| {
+            if let Some((_, quirk)) = QUIRKS.iter().find(|(quirk, _)| quirk == name) {
+              quirks.set(*quirk, value.as_bool().unwrap_or(false));
+            }
+
+            quirks
+          })
+      });
+
+    let keys: Vec<(String, u8)> = rom
+      .get("keys")
+      .and_then(Json::as_object)
+      .unwrap_or_default()
+      .iter()
+      .filter_map(|(name, key)| {
+        let key: i64 = key.as_i64().filter(|key| (0..16).contains(key))?;
+
+        Some((name.clone(), key as u8))
+      })
+      .collect();
+
+    Some(Self {
+      title: program.get("title")?.as_str()?.to_string(),
+      authors: program
+        .get("authors")
+        .and_then(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(Json::as_str)
+        .map(str::to_string)
+        .collect(),
+      platform,
+      quirks,
+      speed: rom
+        .get("tickrate")
+        .and_then(Json::as_i64)
+        .map(|tickrate| tickrate as u64 * FPS),
+      keys,
+    })
+  }
+}
+
+// Parses every ROM of the database - the first program listing a ROM wins.
+fn programs() -> BTreeMap<String, Program> {
+  let mut programs: BTreeMap<String, Program> = BTreeMap::new();
+  let database: Json = Json::parse(DATABASE).unwrap_or(Json::Null);
+
+  for program in database.as_array().unwrap_or_default() {
+    for (hash, rom) in program
+      .get("roms")
+      .and_then(Json::as_object)
+      .unwrap_or_default()
+    {
+      if programs.contains_key(hash) {
+        continue;
+      }
+
+      if let Some(parsed) = Program::parse(program, rom) {
+        programs.insert(hash.clone(), parsed);
+      }
+    }
+  }
+
+  programs
+}
+
+// Guesses the platform of an unknown ROM loaded at `base` from the instructions
+// reachable from its entry point. Following jumps, calls and skips keeps sprite
+// data such as `00 FF` from being mistaken for SCHIP instructions.
+pub fn detect(rom: &[u8], base: u16) -> Platform {
+  let mut platform: Platform = Platform::Chip8;
+  let mut visited: Vec<bool> = vec![false; rom.len()];
+  let mut pending: Vec<usize> = vec![0];
+
+  let target = |address: u16| -> Option<usize> {
+    Some(address.checked_sub(base)? as usize).filter(|offset| *offset < rom.len())
+  };
+
+  while let Some(offset) = pending.pop() {
+    if offset + 1 >= rom.len() || visited[offset] {
+      continue;
+    }
+
+    visited[offset] = true;
+
+    let opcode: u16 = u16::from_be_bytes([rom[offset], rom[offset + 1]]);
+    let next: usize = offset + 2;
+
+    platform = platform.max(instruction_platform(opcode));
+
+    match opcode & 0xF000 {
+      0x0000 if opcode == 0x00EE || opcode == 0x00FD => {}
+      0x1000 => pending.extend(target(nnn!(opcode))),
+      0x2000 => {
+        pending.push(next);
+        pending.extend(target(nnn!(opcode)));
+      }
+      0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 => {
+        pending.push(next);
+        pending.push(next + 2);
+      }
+      0xB000 => {}
+      0xF000 if opcode == 0xF000 => pending.push(next + 2),
+      _ => pending.push(next),
+    }
+  }
+
+  platform
+}
+
+// Oldest platform that defines an instruction.
+fn instruction_platform(opcode: u16) -> Platform {
+  match opcode {
+    0x00FB..=0x00FF => Platform::SuperChip,
+    0x00C1..=0x00CF => Platform::SuperChip,
+    0x00D1..=0x00DF => Platform::XOChip,
+    0xF000 | 0xF002 => Platform::XOChip,
+    _ => match (opcode & 0xF000, opcode & 0x00FF) {
+      (0xD000, _) if n!(opcode) == 0 => Platform::SuperChip,
+      (0x5000, _) if matches!(n!(opcode), 2 | 3) => Platform::XOChip,
+      (0xF000, 0x30) | (0xF000, 0x75) | (0xF000, 0x85) => Platform::SuperChip,
+      (0xF000, 0x01) | (0xF000, 0x3A) => Platform::XOChip,
+      _ => Platform::Chip8,
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sha1::sha1;
+
+  #[test]
+  fn test_lookup() {
+    let pong: Program = Program::lookup("1830eb401ba8789a477dfcf294873a5479ebcfe8").unwrap();
+
+    assert_eq!(pong.title, "Pong");
+    assert_eq!(pong.platform, Platform::Chip8);
+    assert_eq!(Program::lookup(&sha1(b"unknown")), None);
+  }
+
+  #[test]
+  fn test_bindings() {
+    let pong: Program = Program::lookup("1830eb401ba8789a477dfcf294873a5479ebcfe8").unwrap();
+
+    assert!(pong.bindings().contains(&(Input::Button(11), 0x1)));
+  }
+
+  #[test]
+  fn test_detect() {
+    // CLS; JP 0x206; sprite data 00FF; HIGH; JP 0x208
+    let rom: [u8; 10] = [0x00, 0xE0, 0x12, 0x06, 0x00, 0xFF, 0x00, 0xFF, 0x12, 0x08];
+
+    assert_eq!(detect(&rom[..6], 0x200), Platform::Chip8);
+    assert_eq!(detect(&rom, 0x200), Platform::SuperChip);
+    assert_eq!(
+      detect(&[0xF0, 0x00, 0x12, 0x34, 0x50, 0x12], 0x200),
+      Platform::XOChip
+    );
+  }
+}
@@ -0,0 +1,727 @@
+use core::str::from_utf8;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+
+use crate::chip8::Chip8;
+use crate::journal;
+use crate::journal::Target;
+use crate::number::parse_hex;
+use crate::symbols::Symbols;
+
+// Register numbering exposed to the debugger: V0-VF, I, PC, SP, DT, ST
+const REG_I: usize = 0x10;
+const REG_PC: usize = 0x11;
+const REG_SP: usize = 0x12;
+const REG_DT: usize = 0x13;
+const REG_ST: usize = 0x14;
+const REGISTERS: usize = 0x15;
+
+const SIGINT: u8 = 0x02;
+const SIGTRAP: u8 = 0x05;
+
+const TARGET_XML: &str = concat!(
+  "<?xml version=\"1.0\"?>",
+  "<!DOCTYPE target SYSTEM \"gdb-target.dtd\">",
+  "<target version=\"1.0\">",
+  "<feature name=\"org.chip8.core\">",
+  "<reg name=\"v0\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v1\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v2\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v3\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v4\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v5\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v6\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v7\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v8\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"v9\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"va\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"vb\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"vc\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"vd\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"ve\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"vf\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"i\" bitsize=\"16\" type=\"data_ptr\"/>",
+  "<reg name=\"pc\" bitsize=\"16\" type=\"code_ptr\"/>",
+  "<reg name=\"sp\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"dt\" bitsize=\"8\" type=\"uint8\"/>",
+  "<reg name=\"st\" bitsize=\"8\" type=\"uint8\"/>",
+  "</feature>",
+  "</target>",
+);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GdbState {
+  Detached, // no debugger attached - execution is not restricted
+  Halted,   // execution is stopped until the debugger resumes it
+  Stepping, // a single instruction is executed before halting again
+  Running,  // execution continues until a breakpoint is reached
+  Killed,   // the debugger requested the target to terminate
+}
+
+// GDB Remote Serial Protocol server for a single debugger connection.
+//
+// The stub never drives execution itself - the owner calls `poll` to service
+// the connection and asks `before_step`/`after_step` around every `Chip8::step`
+// so the same stub works under `ChipRunner` and in headless mode.
+#[derive(Debug)]
+pub struct GdbStub {
+  listener: TcpListener,
+  stream: Option<TcpStream>,
+  buffer: Vec<u8>,
+  state: GdbState,
+  resume: bool, // flag set if the breakpoint at PC is skipped when resuming
+}
+
+impl GdbStub {
+  pub fn bind(port: u16) -> IoResult<Self> {
+    let listener: TcpListener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+
+    listener.set_nonblocking(true)?;
+
+    Ok(Self {
+      listener,
+      stream: None,
+      buffer: Vec::new(),
+      state: GdbState::Detached,
+      resume: false,
+    })
+  }
+
+  pub fn port(&self) -> u16 {
+    self.listener.local_addr().map_or(0, |addr| addr.port())
+  }
+
+  #[inline]
+  pub fn state(&self) -> GdbState {
+    self.state
+  }
+
+  #[inline]
+  pub fn is_killed(&self) -> bool {
+    self.state == GdbState::Killed
+  }
+
+  // Accepts a pending connection and processes every packet received so far.
+  pub fn poll(&mut self, chip8: &mut Chip8) {
+    if self.state == GdbState::Killed {
+      return;
+    }
+
+    if self.stream.is_none() {
+      self.accept();
+    }
+
+    let closed: bool = match self.receive() {
+      Ok(()) => false,
+      Err(error) => error.kind() != ErrorKind::WouldBlock,
+    };
+
+    while let Some(packet) = self.next_packet() {
+      match packet {
+        Packet::Interrupt => self.halt(SIGINT),
+        Packet::Command(command) => {
+          let reply: Option<String> = self.handle(chip8, &command);
+
+          if let Some(reply) = reply {
+            self.send(&reply);
+          }
+        }
+      }
+    }
+
+    if closed && self.stream.is_some() {
+      self.detach(chip8);
+    }
+  }
+
+  // Returns true if the instruction at PC is allowed to execute.
+  pub fn before_step(&mut self, chip8: &Chip8) -> bool {
+    match self.state {
+      GdbState::Detached | GdbState::Stepping => true,
+      GdbState::Halted | GdbState::Killed => false,
+      GdbState::Running if self.resume => true,
+      GdbState::Running if chip8.is_breakpoint(chip8.pc) => {
+        self.halt(SIGTRAP);
+        false
+      }
+      GdbState::Running => true,
+    }
+  }
+
+  pub fn after_step(&mut self, _chip8: &Chip8) {
+    self.resume = false;
+
+    if self.state == GdbState::Stepping {
+      self.halt(SIGTRAP);
+    }
+  }
+
+  // ===========================================================================
+  // Connection
+  // ===========================================================================
+
+  fn accept(&mut self) {
+    if let Ok((stream, _)) = self.listener.accept() {
+      if stream.set_nonblocking(true).is_ok() {
+        stream.set_nodelay(true).ok();
+
+        self.stream = Some(stream);
+        self.state = GdbState::Halted;
+        self.buffer.clear();
+      }
+    }
+  }
+
+  fn detach(&mut self, chip8: &mut Chip8) {
+    chip8.clear_breakpoints();
+
+    self.stream = None;
+    self.buffer.clear();
+    self.resume = false;
+
+    if self.state != GdbState::Killed {
+      self.state = GdbState::Detached;
+    }
+  }
+
+  fn receive(&mut self) -> IoResult<()> {
+    let mut chunk: [u8; 0x400] = [0; 0x400];
+
+    if let Some(stream) = self.stream.as_mut() {
+      loop {
+        match stream.read(&mut chunk)? {
+          0 => return Err(ErrorKind::UnexpectedEof.into()),
+          size => self.buffer.extend_from_slice(&chunk[..size]),
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn next_packet(&mut self) -> Option<Packet> {
+    loop {
+      match self.buffer.first()? {
+        b'$' => break,
+        0x03 => {
+          self.buffer.remove(0);
+          return Some(Packet::Interrupt);
+        }
+        _ => {
+          self.buffer.remove(0); // acknowledgements and line noise
+        }
+      }
+    }
+
+    let end: usize = self.buffer.iter().position(|&byte| byte == b'#')?;
+
+    if self.buffer.len() < end + 3 {
+      return None;
+    }
+
+    let packet: Vec<u8> = self.buffer.drain(..end + 3).collect();
+    let data: &[u8] = &packet[1..end];
+    let checksum: Option<u8> = from_utf8(&packet[end + 1..])
+      .ok()
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+    if checksum == Some(Self::checksum(data)) {
+      self.write_raw(b"+");
+      Some(Packet::Command(String::from_utf8_lossy(data).into_owned()))
+    } else {
+      self.write_raw(b"-");
+      None
+    }
+  }
+
+  fn send(&mut self, data: &str) {
+    let packet: String = format!("${}#{:02x}", data, Self::checksum(data.as_bytes()));
+
+    self.write_raw(packet.as_bytes());
+  }
+
+  fn write_raw(&mut self, bytes: &[u8]) {
+    if let Some(stream) = self.stream.as_mut() {
+      let mut offset: usize = 0;
+
+      while offset < bytes.len() {
+        match stream.write(&bytes[offset..]) {
+          Ok(size) => offset += size,
+          Err(ref error) if error.kind() == ErrorKind::WouldBlock => continue,
+          Err(_) => break,
+        }
+      }
+    }
+  }
+
+  fn halt(&mut self, signal: u8) {
+    if self.stream.is_some() {
+      self.state = GdbState::Halted;
+      self.send(&format!("S{:02x}", signal));
+    }
+  }
+
+  fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum: u8, &byte| sum.wrapping_add(byte))
+  }
+
+  // ===========================================================================
+  // Commands
+  // ===========================================================================
+
+  fn handle(&mut self, chip8: &mut Chip8, command: &str) -> Option<String> {
+    let (head, tail): (char, &str) = match command.chars().next() {
+      Some(head) => (head, &command[head.len_utf8()..]),
+      None => return Some(String::new()),
+    };
+
+    let reply: String = match head {
+      '?' => format!("S{:02x}", SIGTRAP),
+      'g' => read_registers(chip8),
+      'G' => ok(write_registers(chip8, tail)),
+      'p' => match parse_hex(tail) {
+        Some(index) => read_register(chip8, index as usize).unwrap_or_else(error),
+        None => error(),
+      },
+      'P' => ok(split_pair(tail, '=').and_then(|(index, value)| {
+        write_register(chip8, parse_hex(index)? as usize, &decode_hex(value)?)
+      })),
+      'm' => read_memory(chip8, tail).unwrap_or_else(error),
+      'M' => ok(write_memory(chip8, tail)),
+      'Z' | 'z' => self.breakpoint(chip8, head == 'Z', tail),
+      'b' if tail == "s" || tail == "c" => self.reverse(chip8, tail == "c"),
+      'c' | 's' => {
+        if let Some(address) = parse_hex(tail) {
+          if address > journal::limit(Target::Pc) {
+            return Some(error());
+          }
+
+          chip8.pc = address;
+        }
+
+        self.resume = true;
+        self.state = if head == 'c' {
+          GdbState::Running
+        } else {
+          GdbState::Stepping
+        };
+
+        return None;
+      }
+      'k' => {
+        self.detach(chip8);
+        self.state = GdbState::Killed;
+        return None;
+      }
+      'D' => {
+        self.send("OK");
+        self.detach(chip8);
+        return None;
+      }
+      'H' => "OK".to_string(),
+      'q' if tail.starts_with("Rcmd,") => monitor(chip8, &tail[5..]),
+      'q' => query(tail),
+      _ => String::new(),
+    };
+
+    Some(reply)
+  }
+
+  // Steps backwards through the undo journal, a single instruction or until a breakpoint.
+  fn reverse(&mut self, chip8: &mut Chip8, until_breakpoint: bool) -> String {
+    self.state = GdbState::Halted;
+
+    loop {
+      if !chip8.step_back() {
+        return format!("T{:02x}replaylog:begin;", SIGTRAP);
+      }
+
+      if !until_breakpoint || chip8.is_breakpoint(chip8.pc) {
+        return format!("S{:02x}", SIGTRAP);
+      }
+    }
+  }
+
+  fn breakpoint(&mut self, chip8: &mut Chip8, insert: bool, args: &str) -> String {
+    let mut parts = args.split(',');
+
+    // Only software breakpoints are supported
+    if parts.next() != Some("0") {
+      return String::new();
+    }
+
+    match parts.next().and_then(parse_hex) {
+      Some(address) if insert => chip8.set_breakpoint(address),
+      Some(address) => chip8.clear_breakpoint(address),
+      None => return error(),
+    }
+
+    "OK".to_string()
+  }
+}
+
+enum Packet {
+  Interrupt,
+  Command(String),
+}
+
+fn query(query: &str) -> String {
+  if query.starts_with("Supported") {
+    "PacketSize=1000;qXfer:features:read+;ReverseStep+;ReverseContinue+".to_string()
+  } else if query == "Attached" {
+    "1".to_string()
+  } else if query == "C" {
+    "QC1".to_string()
+  } else if query == "fThreadInfo" {
+    "m1".to_string()
+  } else if query == "sThreadInfo" {
+    "l".to_string()
+  } else if let Some(args) = query.strip_prefix("Xfer:features:read:target.xml:") {
+    match split_pair(args, ',')
+      .and_then(|(offset, size)| Some((parse_hex(offset)?, parse_hex(size)?)))
+    {
+      Some((offset, size)) => {
+        let offset: usize = (offset as usize).min(TARGET_XML.len());
+        let end: usize = (offset + size as usize).min(TARGET_XML.len());
+        let mark: char = if end == TARGET_XML.len() { 'l' } else { 'm' };
+
+        format!("{}{}", mark, &TARGET_XML[offset..end])
+      }
+      None => error(),
+    }
+  } else {
+    String::new()
+  }
+}
+
+// Answers `monitor <query>` commands with the undo journal, ie. `monitor V3 == 0`.
+fn monitor(chip8: &Chip8, command: &str) -> String {
+  let command: String = match decode_hex(command).and_then(|bytes| String::from_utf8(bytes).ok()) {
+    Some(command) => command,
+    None => return error(),
+  };
+
+  let output: String = match chip8.journal.as_ref() {
+    Some(journal) => journal.answer(&command, &Symbols::new()),
+    None => "Journal Disabled".to_string(),
+  };
+
+  encode_hex(format!("{}\n", output).as_bytes())
+}
+
+fn read_registers(chip8: &Chip8) -> String {
+  (0..REGISTERS)
+    .filter_map(|index| read_register(chip8, index))
+    .collect()
+}
+
+// Writes every register - nothing is written if any value is out of range.
+fn write_registers(chip8: &mut Chip8, data: &str) -> Option<()> {
+  let bytes: Vec<u8> = decode_hex(data)?;
+  let mut values: Vec<(Target, u16)> = Vec::with_capacity(REGISTERS);
+  let mut offset: usize = 0;
+
+  for index in 0..REGISTERS {
+    let size: usize = register_size(index);
+
+    values.push(decode_register(index, bytes.get(offset..offset + size)?)?);
+    offset += size;
+  }
+
+  for (target, value) in values {
+    journal::write(chip8, target, value);
+  }
+
+  Some(())
+}
+
+fn read_register(chip8: &Chip8, index: usize) -> Option<String> {
+  let value: u16 = match index {
+    0x0..=0xF => chip8.reg_v[index] as u16,
+    REG_I => chip8.reg_i,
+    REG_PC => chip8.pc,
+    REG_SP => chip8.sp as u16,
+    REG_DT => chip8.delay as u16,
+    REG_ST => chip8.sound as u16,
+    _ => return None,
+  };
+
+  // Multi-byte registers are transferred in little-endian order
+  Some(encode_hex(&value.to_le_bytes()[..register_size(index)]))
+}
+
+fn write_register(chip8: &mut Chip8, index: usize, bytes: &[u8]) -> Option<()> {
+  let (target, value): (Target, u16) = decode_register(index, bytes)?;
+
+  journal::write(chip8, target, value);
+
+  Some(())
+}
+
+// Returns the register and value of a register write - `None` for values the
+// interpreter cannot run with (see `journal::limit`).
+fn decode_register(index: usize, bytes: &[u8]) -> Option<(Target, u16)> {
+  if index >= REGISTERS || bytes.len() != register_size(index) {
+    return None;
+  }
+
+  let value: u16 = bytes
+    .iter()
+    .rev()
+    .fold(0, |value, &byte| value << 8 | byte as u16);
+
+  let target: Target = match index {
+    0x0..=0xF => Target::V(index as u8),
+    REG_I => Target::I,
+    REG_PC => Target::Pc,
+    REG_SP => Target::Sp,
+    REG_DT => Target::Dt,
+    REG_ST => Target::St,
+    _ => return None,
+  };
+
+  Some((target, value)).filter(|(target, value)| *value <= journal::limit(*target))
+}
+
+#[inline]
+fn register_size(index: usize) -> usize {
+  if index == REG_I || index == REG_PC {
+    2
+  } else {
+    1
+  }
+}
+
+fn read_memory(chip8: &Chip8, args: &str) -> Option<String> {
+  let (address, size): (usize, usize) = parse_range(args)?;
+
+  chip8.memory.get(address..address + size).map(encode_hex)
+}
+
+fn write_memory(chip8: &mut Chip8, args: &str) -> Option<()> {
+  let (range, data): (&str, &str) = split_pair(args, ':')?;
+  let (address, size): (usize, usize) = parse_range(range)?;
+  let bytes: Vec<u8> = decode_hex(data)?;
+
+  if bytes.len() != size {
+    return None;
+  }
+
+  journal::edit_memory(chip8, address, &bytes).ok()
+}
+
+fn parse_range(args: &str) -> Option<(usize, usize)> {
+  let (address, size): (&str, &str) = split_pair(args, ',')?;
+
+  Some((parse_hex(address)? as usize, parse_hex(size)? as usize))
+}
+
+fn split_pair(data: &str, separator: char) -> Option<(&str, &str)> {
+  let index: usize = data.find(separator)?;
+
+  Some((&data[..index], &data[index + 1..]))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(data: &str) -> Option<Vec<u8>> {
+  if !data.len().is_multiple_of(2) {
+    return None;
+  }
+
+  (0..data.len())
+    .step_by(2)
+    .map(|index| u8::from_str_radix(data.get(index..index + 2)?, 16).ok())
+    .collect()
+}
+
+fn ok(result: Option<()>) -> String {
+  match result {
+    Some(()) => "OK".to_string(),
+    None => error(),
+  }
+}
+
+fn error() -> String {
+  "E01".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread::spawn;
+  use std::thread::yield_now;
+  use std::thread::JoinHandle;
+
+  // LD V0, 2A - LD V1, 05 - JP 0x204
+  const ROM: [u8; 6] = [0x60, 0x2A, 0x61, 0x05, 0x12, 0x04];
+
+  fn request(stream: &mut TcpStream, command: &str) -> String {
+    let packet: String = format!("${}#{:02x}", command, GdbStub::checksum(command.as_bytes()));
+    let mut reply: Vec<u8> = Vec::new();
+    let mut byte: [u8; 1] = [0];
+
+    stream.write_all(packet.as_bytes()).unwrap();
+
+    while reply.last() != Some(&b'#') {
+      stream.read_exact(&mut byte).unwrap();
+
+      if byte[0] != b'+' || !reply.is_empty() {
+        reply.push(byte[0]);
+      }
+    }
+
+    let mut checksum: [u8; 2] = [0; 2];
+
+    stream.read_exact(&mut checksum).unwrap();
+    stream.write_all(b"+").unwrap();
+
+    String::from_utf8(reply[1..reply.len() - 1].to_vec()).unwrap()
+  }
+
+  fn session(port: u16, script: &'static [&'static str]) -> JoinHandle<Vec<String>> {
+    spawn(move || {
+      let mut stream: TcpStream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+      let mut replies: Vec<String> = Vec::new();
+
+      for command in script {
+        replies.push(request(&mut stream, command));
+      }
+
+      stream.write_all(b"$k#6b").unwrap();
+
+      replies
+    })
+  }
+
+  fn serve(gdb: &mut GdbStub, chip8: &mut Chip8) {
+    while !gdb.is_killed() {
+      gdb.poll(chip8);
+
+      if gdb.state() != GdbState::Detached && gdb.before_step(chip8) {
+        chip8.step();
+        gdb.after_step(chip8);
+      }
+
+      yield_now();
+    }
+  }
+
+  #[test]
+  fn test_registers() {
+    let mut chip8: Chip8 = Chip8::new();
+    let mut gdb: GdbStub = GdbStub::bind(0).unwrap();
+
+    chip8.load_bytes(&ROM, false).unwrap();
+
+    let client = session(
+      gdb.port(),
+      &["?", "p11", "s", "p0", "P3=7f", "p3", "P10=3402", "p10", "g"],
+    );
+
+    serve(&mut gdb, &mut chip8);
+
+    let replies: Vec<String> = client.join().unwrap();
+
+    assert_eq!(replies[0], "S05");
+    assert_eq!(replies[1], "0002");
+    assert_eq!(replies[2], "S05");
+    assert_eq!(replies[3], "2a");
+    assert_eq!(replies[4], "OK");
+    assert_eq!(replies[5], "7f");
+    assert_eq!(replies[6], "OK");
+    assert_eq!(replies[7], "3402");
+    assert_eq!(replies[8], "2a00007f00000000000000000000000034020202000000");
+    assert_eq!(chip8.reg_i, 0x0234);
+  }
+
+  #[test]
+  fn test_register_limits() {
+    let mut chip8: Chip8 = Chip8::new();
+    let mut gdb: GdbStub = GdbStub::bind(0).unwrap();
+
+    chip8.load_bytes(&ROM, false).unwrap();
+
+    let client = session(
+      gdb.port(),
+      &[
+        "P11=fe0f",
+        "P12=10",
+        "P10=ff0f",
+        "G2a00007f00000000000000000000000034120202000000",
+        "p3",
+        "cfff",
+      ],
+    );
+
+    serve(&mut gdb, &mut chip8);
+
+    let replies: Vec<String> = client.join().unwrap();
+
+    assert_eq!(replies[0], "E01");
+    assert_eq!(replies[1], "E01");
+    assert_eq!(replies[2], "E01");
+    assert_eq!(replies[3], "E01");
+    assert_eq!(replies[4], "00");
+    assert_eq!(replies[5], "E01");
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.sp, 0x0);
+  }
+
+  #[test]
+  fn test_memory() {
+    let mut chip8: Chip8 = Chip8::new();
+    let mut gdb: GdbStub = GdbStub::bind(0).unwrap();
+
+    chip8.load_bytes(&ROM, false).unwrap();
+
+    let client = session(gdb.port(), &["m200,4", "M300,2:abcd", "m300,2", "mfff0,2"]);
+
+    serve(&mut gdb, &mut chip8);
+
+    let replies: Vec<String> = client.join().unwrap();
+
+    assert_eq!(replies[0], "602a6105");
+    assert_eq!(replies[1], "OK");
+    assert_eq!(replies[2], "abcd");
+    assert_eq!(replies[3], "E01");
+  }
+
+  #[test]
+  fn test_breakpoints() {
+    let mut chip8: Chip8 = Chip8::new();
+    let mut gdb: GdbStub = GdbStub::bind(0).unwrap();
+
+    chip8.load_bytes(&ROM, false).unwrap();
+
+    let client = session(
+      gdb.port(),
+      &[
+        "Z0,202,2", "c", "p11", "p1", "z0,202,2", "Z0,204,2", "c", "p11", "p1",
+      ],
+    );
+
+    serve(&mut gdb, &mut chip8);
+
+    let replies: Vec<String> = client.join().unwrap();
+
+    assert_eq!(replies[0], "OK");
+    assert_eq!(replies[1], "S05");
+    assert_eq!(replies[2], "0202");
+    assert_eq!(replies[3], "00");
+    assert_eq!(replies[4], "OK");
+    assert_eq!(replies[5], "OK");
+    assert_eq!(replies[6], "S05");
+    assert_eq!(replies[7], "0402");
+    assert_eq!(replies[8], "05");
+    assert!(chip8.breakpoints.is_empty());
+  }
+}
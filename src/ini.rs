@@ -0,0 +1,183 @@
+use std::fs::read_to_string;
+use std::fs::write;
+
+// Settings file made of `[section]` headers and `key = value` entries.
+//
+// Entries before the first header belong to the unnamed section `""`. Keys may
+// repeat and keep their file order. Lines starting with `#` or `;` are ignored.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Ini {
+  sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Ini {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn load(path: &str) -> Result<Self, &'static str> {
+    read_to_string(path)
+      .map_err(|_| "Invalid Config File")
+      .and_then(|input| Self::parse(&input))
+  }
+
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    let mut this: Self = Self::new();
+
+    for line in input.lines().map(str::trim) {
+      if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+        continue;
+      }
+
+      if line.starts_with('[') {
+        let name: &str = line
+          .strip_prefix('[')
+          .and_then(|line| line.strip_suffix(']'))
+          .ok_or("Invalid Config Section")?;
+
+        this.sections.push((name.trim().to_string(), Vec::new()));
+        continue;
+      }
+
+      let (key, value): (&str, &str) = match line.find('=') {
+        Some(index) => (&line[..index], &line[index + 1..]),
+        None => return Err("Invalid Config Entry"),
+      };
+
+      if this.sections.is_empty() {
+        this.sections.push((String::new(), Vec::new()));
+      }
+
+      if let Some((_, entries)) = this.sections.last_mut() {
+        entries.push((key.trim().to_string(), value.trim().to_string()));
+      }
+    }
+
+    Ok(this)
+  }
+
+  // Sets `key` in a section of a config file, creating the file if missing.
+  pub fn store(path: &str, section: &str, key: &str, value: &str) -> Result<(), &'static str> {
+    let input: String = read_to_string(path).unwrap_or_default();
+
+    write(path, Self::update(&input, section, key, value)).map_err(|_| "Invalid Config File")
+  }
+
+  // Replaces the value of `key` in a section, or adds the entry after the last
+  // one of the section. Comments and the order of other lines are kept.
+  pub fn update(input: &str, section: &str, key: &str, value: &str) -> String {
+    let entry: String = format!("{} = {}", key, value);
+    let mut lines: Vec<String> = input.lines().map(str::to_string).collect();
+    let mut current: String = String::new();
+    let mut end: Option<usize> = if section.is_empty() { Some(0) } else { None };
+    let mut found: bool = false;
+
+    for (index, line) in lines.iter_mut().enumerate() {
+      let trimmed: &str = line.trim();
+
+      if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+        continue;
+      }
+
+      if trimmed.starts_with('[') {
+        current = trimmed
+          .trim_matches(|char| char == '[' || char == ']')
+          .trim()
+          .to_string();
+
+        if current == section && end.is_none() {
+          end = Some(index + 1);
+        }
+
+        continue;
+      }
+
+      if current != section {
+        continue;
+      }
+
+      end = Some(index + 1);
+
+      if trimmed.split('=').next().map(str::trim) == Some(key) {
+        *line = entry.clone();
+        found = true;
+      }
+    }
+
+    match end {
+      _ if found => {}
+      Some(index) => lines.insert(index, entry),
+      None => {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+          lines.push(String::new());
+        }
+
+        lines.push(format!("[{}]", section));
+        lines.push(entry);
+      }
+    }
+
+    lines.join("\n") + "\n"
+  }
+
+  // Returns the names of all sections in file order.
+  pub fn sections(&self) -> impl Iterator<Item = &str> {
+    self.sections.iter().map(|(name, _)| name.as_str())
+  }
+
+  // Returns all entries of a section - sections that appear twice are merged.
+  pub fn section(&self, name: &str) -> Vec<(&str, &str)> {
+    self
+      .sections
+      .iter()
+      .filter(|(section, _)| section == name)
+      .flat_map(|(_, entries)| entries.iter())
+      .map(|(key, value)| (key.as_str(), value.as_str()))
+      .collect()
+  }
+
+  // Returns the last value of `key` in a section.
+  pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+    self
+      .section(section)
+      .into_iter()
+      .rev()
+      .find(|(name, _)| *name == key)
+      .map(|(_, value)| value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let ini: Ini = Ini::parse(
+      "speed = 700\n# comment\n[keymap]\n5 = w, up\n\n[keymap.PONG]\n1 = q\n[keymap]\n5 = s\n",
+    )
+    .unwrap();
+
+    assert_eq!(ini.get("", "speed"), Some("700"));
+    assert_eq!(ini.get("keymap", "5"), Some("s"));
+    assert_eq!(ini.section("keymap"), vec![("5", "w, up"), ("5", "s")]);
+    assert_eq!(
+      ini.sections().collect::<Vec<_>>(),
+      vec!["", "keymap", "keymap.PONG", "keymap"]
+    );
+    assert_eq!(Ini::parse("[keymap"), Err("Invalid Config Section"));
+    assert_eq!(Ini::parse("speed"), Err("Invalid Config Entry"));
+    assert_eq!(
+      Ini::update("# chip\nspeed = 700\n[rom.PONG]\n", "", "theme", "amber"),
+      "# chip\nspeed = 700\ntheme = amber\n[rom.PONG]\n"
+    );
+    assert_eq!(
+      Ini::update("theme = lcd\n[keymap]\n", "", "theme", "octo"),
+      "theme = octo\n[keymap]\n"
+    );
+    assert_eq!(
+      Ini::update("speed = 700\n", "keymap", "5", "w"),
+      "speed = 700\n\n[keymap]\n5 = w\n"
+    );
+  }
+}
@@ -11,6 +11,29 @@ impl<'a> Instruction<'a> {
       .iter()
       .find(|instruction| instruction.mask & opcode == instruction.code)
   }
+
+  // Renders the instruction with the operands of `opcode` filled in.
+  pub fn disassemble(&self, opcode: u16) -> String {
+    self.disassemble_with(opcode, &format!("{:04X}", nnn!(opcode)))
+  }
+
+  // Renders the instruction with the address operand replaced by `addr`.
+  pub fn disassemble_with(&self, opcode: u16, addr: &str) -> String {
+    let operands: String = self
+      .desc
+      .replace("Vx", &format!("V{:X}", x!(opcode)))
+      .replace("Vy", &format!("V{:X}", y!(opcode)))
+      .replace("nibble", &format!("{:X}", n!(opcode)))
+      .replace("byte", &format!("{:02X}", kk!(opcode)))
+      .replace("addr", addr);
+
+    format!(
+      "{name:<padding$}{operands}",
+      name = self.name,
+      operands = operands,
+      padding = if operands.is_empty() { 0 } else { 8 },
+    )
+  }
 }
 
 const INSTRUCTIONS: &[Instruction] = &[
@@ -0,0 +1,692 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FResult;
+use core::ptr::null_mut;
+use std::collections::VecDeque;
+
+use crate::chip8::Chip8;
+use crate::chip8::Pitch;
+use crate::chip8::RAM;
+use crate::chip8::STACK;
+use crate::coverage::Access;
+use crate::instruction::Instruction;
+use crate::number::parse_number;
+use crate::symbols::Symbols;
+
+const ENTRIES: usize = 0x10000; // instructions kept before the oldest are dropped
+const NO_WAIT: u16 = 0xFFFF; // value of `Target::Wait` while no key is awaited
+
+// A single piece of interpreter state tracked by the journal.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Target {
+  Pc,
+  V(u8),
+  R(u8),
+  I,
+  Sp,
+  Dt,
+  St,
+  Stack(u8),
+  Call(u8),
+  Wait,
+  Underflow,
+  Overflow,
+  Pitch,
+  Memory(u16),
+  Pixel(u16),
+}
+
+impl Target {
+  // Parses a register name (`V0`-`VF`, `R0`-`R7`, `I`, `PC`, `SP`, `DT`, `ST`), a
+  // stack flag (`UNDERFLOW`, `OVERFLOW`) or `[ADDR]`.
+  pub fn parse(input: &str) -> Option<Self> {
+    let input: String = input.trim().to_ascii_uppercase();
+
+    match input.as_str() {
+      "PC" => Some(Self::Pc),
+      "I" => Some(Self::I),
+      "SP" => Some(Self::Sp),
+      "DT" => Some(Self::Dt),
+      "ST" => Some(Self::St),
+      "UNDERFLOW" => Some(Self::Underflow),
+      "OVERFLOW" => Some(Self::Overflow),
+      name if name.starts_with('[') && name.ends_with(']') => {
+        parse_number(&name[1..name.len() - 1]).map(Self::Memory)
+      }
+      name if name.len() == 2 && name.starts_with('V') => {
+        u8::from_str_radix(&name[1..], 16).ok().map(Self::V)
+      }
+      name if name.len() == 2 && name.starts_with('R') => {
+        u8::from_str_radix(&name[1..], 8).ok().map(Self::R)
+      }
+      _ => None,
+    }
+  }
+}
+
+impl Display for Target {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    match self {
+      Self::Pc => f.write_str("PC"),
+      Self::V(index) => write!(f, "V{:X}", index),
+      Self::R(index) => write!(f, "R{:X}", index),
+      Self::I => f.write_str("I"),
+      Self::Sp => f.write_str("SP"),
+      Self::Dt => f.write_str("DT"),
+      Self::St => f.write_str("ST"),
+      Self::Stack(index) => write!(f, "S{:X}", index),
+      Self::Call(index) => write!(f, "C{:X}", index),
+      Self::Wait => f.write_str("WAIT"),
+      Self::Underflow => f.write_str("UNDERFLOW"),
+      Self::Overflow => f.write_str("OVERFLOW"),
+      Self::Pitch => f.write_str("PITCH"),
+      Self::Memory(address) => write!(f, "[{:#06X}]", address),
+      Self::Pixel(index) => write!(
+        f,
+        "PIXEL({}, {})",
+        *index as usize % Chip8::W,
+        *index as usize / Chip8::W
+      ),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Change {
+  pub target: Target,
+  pub old: u16,
+  pub new: u16,
+}
+
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    write!(f, "{} {:#X} -> {:#X}", self.target, self.old, self.new)
+  }
+}
+
+// Every change made by a single instruction.
+//
+// Changes made between instructions (key presses completing an Fx0A wait,
+// register and memory edits from a debugger) are folded into the preceding entry.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Entry {
+  pub cycle: u64,  // value of the cycle counter before the instruction
+  pub pc: u16,     // address of the instruction
+  pub opcode: u16, // executed opcode
+  pub changes: Vec<Change>,
+}
+
+// State captured before an instruction executes.
+struct Pending {
+  entry: Entry,
+  memory: Option<(u16, Vec<u8>)>,
+  display: bool, // flag set if the display was captured before the instruction
+}
+
+// Undo journal allowing execution to be stepped backwards.
+pub struct Journal {
+  targets: Vec<Target>,     // register state compared around every instruction
+  entries: VecDeque<Entry>, // executed instructions, oldest first
+  redo: Vec<Entry>,         // instructions undone by `step_back`
+  pending: Option<Pending>, // instruction currently executing
+  before: Vec<u16>,         // register state before the pending instruction
+  after: Vec<u16>,          // register state after the most recent entry - empty if none
+  display: Vec<u8>,         // display before the pending instruction
+}
+
+impl Journal {
+  pub fn new() -> Self {
+    let mut targets: Vec<Target> = vec![
+      Target::Pc,
+      Target::I,
+      Target::Sp,
+      Target::Dt,
+      Target::St,
+      Target::Wait,
+      Target::Underflow,
+      Target::Overflow,
+      Target::Pitch,
+    ];
+
+    targets.extend((0..0x10).map(Target::V));
+    targets.extend((0..0x8).map(Target::R));
+    targets.extend((0..0x10).map(Target::Stack));
+    targets.extend((0..0x10).map(Target::Call));
+
+    Self {
+      targets,
+      entries: VecDeque::new(),
+      redo: Vec::new(),
+      pending: None,
+      before: Vec::new(),
+      after: Vec::new(),
+      display: Vec::new(),
+    }
+  }
+
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  #[inline]
+  pub fn entries(&self) -> impl DoubleEndedIterator<Item = &Entry> {
+    self.entries.iter()
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+    self.redo.clear();
+    self.pending = None;
+    self.after.clear();
+  }
+
+  // Captures the state touched by the instruction about to execute.
+  pub(crate) fn begin(&mut self, chip8: &Chip8, opcode: u16) {
+    self.sync(chip8);
+    self.redo.clear();
+
+    let memory: Option<(u16, Vec<u8>)> = match chip8.access(opcode) {
+      Some((access, address, length)) if access.contains(Access::WRITE) => Some((
+        address,
+        (0..length)
+          .map(|offset| byte(chip8, address + offset))
+          .collect(),
+      )),
+      _ => None,
+    };
+
+    let display: bool = touches_display(opcode);
+
+    if display {
+      self.display.clear();
+      self.display.extend_from_slice(&chip8.display);
+    }
+
+    self.pending = Some(Pending {
+      entry: Entry {
+        cycle: chip8.cycles,
+        pc: chip8.pc,
+        opcode,
+        changes: Vec::new(),
+      },
+      memory,
+      display,
+    });
+  }
+
+  // Records the changes made by the instruction captured with `begin`.
+  pub(crate) fn commit(&mut self, chip8: &Chip8) {
+    let pending: Pending = match self.pending.take() {
+      Some(pending) => pending,
+      None => return,
+    };
+
+    let mut entry: Entry = pending.entry;
+
+    capture(&self.targets, chip8, &mut self.after);
+    entry.changes = self.diff(&self.before, &self.after);
+
+    if let Some((address, bytes)) = pending.memory {
+      for (offset, old) in bytes.into_iter().enumerate() {
+        let address: u16 = address + offset as u16;
+
+        entry.changes.push(Change {
+          target: Target::Memory(address),
+          old: old as u16,
+          new: byte(chip8, address) as u16,
+        });
+      }
+    }
+
+    if pending.display {
+      for (index, (old, new)) in self.display.iter().zip(chip8.display.iter()).enumerate() {
+        if old != new {
+          entry.changes.push(Change {
+            target: Target::Pixel(index as u16),
+            old: *old as u16,
+            new: *new as u16,
+          });
+        }
+      }
+    }
+
+    if self.entries.len() == ENTRIES {
+      self.entries.pop_front();
+    }
+
+    self.entries.push_back(entry);
+  }
+
+  // Reverts the most recent instruction - returns false if the journal is empty.
+  pub(crate) fn step_back(&mut self, chip8: &mut Chip8) -> bool {
+    self.sync(chip8);
+
+    let entry: Entry = match self.entries.pop_back() {
+      Some(entry) => entry,
+      None => return false,
+    };
+
+    for change in entry.changes.iter().rev() {
+      write(chip8, change.target, change.old);
+    }
+
+    chip8.cycles = entry.cycle;
+
+    capture(&self.targets, chip8, &mut self.after);
+    self.redo.push(entry);
+
+    true
+  }
+
+  // Re-applies the most recently undone instruction and returns its opcode.
+  pub(crate) fn step_forward(&mut self, chip8: &mut Chip8) -> Option<u16> {
+    self.sync(chip8);
+
+    let entry: Entry = self.redo.pop()?;
+
+    for change in entry.changes.iter() {
+      write(chip8, change.target, change.new);
+    }
+
+    chip8.cycles = entry.cycle + 1;
+
+    let opcode: u16 = entry.opcode;
+
+    capture(&self.targets, chip8, &mut self.after);
+    self.entries.push_back(entry);
+
+    Some(opcode)
+  }
+
+  // Finds the most recent change of `target`, optionally to a specific value.
+  //
+  // Memory writes are recorded even if they store the value already present.
+  pub fn find(&self, target: Target, value: Option<u16>) -> Option<(&Entry, &Change)> {
+    self.entries.iter().rev().find_map(|entry| {
+      entry
+        .changes
+        .iter()
+        .rev()
+        .find(|change| change.target == target && value.is_none_or(|value| change.new == value))
+        .map(|change| (entry, change))
+    })
+  }
+
+  // Parses and runs a query such as `[0x3F0]` (last write) or `V3 == 0` (last
+  // time V3 became zero).
+  pub fn query(&self, input: &str) -> Result<Option<(&Entry, &Change)>, &'static str> {
+    let (target, value): (&str, Option<&str>) = match input.find('=') {
+      Some(index) => (
+        &input[..index],
+        Some(input[index..].trim_start_matches('=')),
+      ),
+      None => (input, None),
+    };
+
+    let target: Target = Target::parse(target).ok_or("Invalid Query Target")?;
+    let value: Option<u16> = match value {
+      Some(value) => Some(parse_number(value.trim()).ok_or("Invalid Query Value")?),
+      None => None,
+    };
+
+    Ok(self.find(target, value))
+  }
+
+  // Runs a query and describes the result for display in a debugger console.
+  pub fn answer(&self, input: &str, symbols: &Symbols) -> String {
+    match self.query(input) {
+      Ok(Some((entry, change))) => format!(
+        "{} at cycle {}, {} ({})",
+        change,
+        entry.cycle,
+        symbols.locate(entry.pc),
+        match Instruction::find(entry.opcode) {
+          Some(instruction) => symbols.disassemble(instruction, entry.opcode),
+          None => format!("{:04X}", entry.opcode),
+        },
+      ),
+      Ok(None) => format!("No match in the last {} instructions", self.len()),
+      Err(error) => error.to_string(),
+    }
+  }
+
+  // Folds a change made since the last instruction into its entry - memory is
+  // not compared by `sync`, so memory edits are recorded as they happen.
+  fn record(&mut self, change: Change) {
+    self.redo.clear();
+
+    if let Some(entry) = self.entries.back_mut() {
+      entry.changes.push(change);
+    }
+  }
+
+  // Folds changes made since the last instruction into its entry and leaves
+  // the current register state in `before`.
+  fn sync(&mut self, chip8: &Chip8) {
+    capture(&self.targets, chip8, &mut self.before);
+
+    if self.after.is_empty() {
+      return;
+    }
+
+    let changes: Vec<Change> = self.diff(&self.after, &self.before);
+
+    if !changes.is_empty() {
+      self.redo.clear();
+
+      if let Some(entry) = self.entries.back_mut() {
+        entry.changes.extend(changes);
+      }
+    }
+
+    self.after.clone_from(&self.before);
+  }
+
+  fn diff(&self, old: &[u16], new: &[u16]) -> Vec<Change> {
+    self
+      .targets
+      .iter()
+      .zip(old.iter().zip(new.iter()))
+      .filter(|(_, (old, new))| old != new)
+      .map(|(target, (old, new))| Change {
+        target: *target,
+        old: *old,
+        new: *new,
+      })
+      .collect()
+  }
+}
+
+impl Default for Journal {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Reads the targets into a buffer, reusing its allocation.
+fn capture(targets: &[Target], chip8: &Chip8, buffer: &mut Vec<u16>) {
+  buffer.clear();
+  buffer.extend(targets.iter().map(|target| read(chip8, *target)));
+}
+
+// Returns whether an opcode can change the display: CLS, the scrolls (00Bn,
+// 00Cn, 00FB, 00FC), LOW and HIGH, and DRW.
+fn touches_display(opcode: u16) -> bool {
+  match opcode {
+    0x00E0 | 0x00FB | 0x00FC | 0x00FE | 0x00FF => true,
+    _ => opcode & 0xFFF0 == 0x00B0 || opcode & 0xFFF0 == 0x00C0 || opcode & 0xF000 == 0xD000,
+  }
+}
+
+fn byte(chip8: &Chip8, address: u16) -> u8 {
+  chip8.memory.get(address as usize).copied().unwrap_or(0)
+}
+
+pub(crate) fn read(chip8: &Chip8, target: Target) -> u16 {
+  match target {
+    Target::Pc => chip8.pc,
+    Target::V(index) => chip8.reg_v[index as usize] as u16,
+    Target::R(index) => chip8.reg_u[index as usize] as u16,
+    Target::I => chip8.reg_i,
+    Target::Sp => chip8.sp as u16,
+    Target::Dt => chip8.delay as u16,
+    Target::St => chip8.sound as u16,
+    Target::Stack(index) => chip8.stack[index as usize],
+    Target::Call(index) => chip8.calls[index as usize],
+    Target::Wait if chip8.wait.is_null() => NO_WAIT,
+    Target::Wait => (chip8.wait as usize - chip8.reg_v.as_ptr() as usize) as u16,
+    Target::Underflow => chip8.underflow as u16,
+    Target::Overflow => chip8.overflow as u16,
+    Target::Pitch => chip8.pitch as u16,
+    Target::Memory(address) => byte(chip8, address) as u16,
+    Target::Pixel(index) => chip8.display[index as usize] as u16,
+  }
+}
+
+pub(crate) fn write(chip8: &mut Chip8, target: Target, value: u16) {
+  match target {
+    Target::Pc => chip8.pc = value,
+    Target::V(index) => chip8.reg_v[index as usize] = value as u8,
+    Target::R(index) => chip8.reg_u[index as usize] = value as u8,
+    Target::I => chip8.reg_i = value,
+    Target::Sp => chip8.sp = value as u8,
+    Target::Dt => chip8.delay = value as u8,
+    Target::St => chip8.sound = value as u8,
+    Target::Stack(index) => chip8.stack[index as usize] = value,
+    Target::Call(index) => chip8.calls[index as usize] = value,
+    Target::Wait if value == NO_WAIT => chip8.wait = null_mut(),
+    Target::Wait => chip8.wait = &mut chip8.reg_v[value as usize],
+    Target::Underflow => chip8.underflow = value != 0,
+    Target::Overflow => chip8.overflow = value != 0,
+    Target::Pitch if value == Pitch::P16 as u16 => chip8.pitch = Pitch::P16,
+    Target::Pitch => chip8.pitch = Pitch::P8,
+    Target::Memory(address) => {
+      if let Some(byte) = chip8.memory.get_mut(address as usize) {
+        *byte = value as u8;
+      }
+    }
+    Target::Pixel(index) => {
+      chip8.display[index as usize] = value as u8;
+      chip8.render = true;
+    }
+  }
+}
+
+// Largest value of a target the interpreter can keep running with - PC must
+// leave room for a whole opcode, I must point into memory and SP must leave a
+// stack entry for the next CALL.
+pub(crate) fn limit(target: Target) -> u16 {
+  match target {
+    Target::Pc => RAM as u16 - 2,
+    Target::I => RAM as u16 - 1,
+    Target::Sp => STACK as u16 - 1,
+    Target::Stack(_) | Target::Call(_) => RAM as u16 - 2,
+    Target::Underflow | Target::Overflow => 1,
+    _ => 0xFF,
+  }
+}
+
+// Writes a value entered in a debugger - values past `limit` are rejected.
+pub(crate) fn edit(chip8: &mut Chip8, target: Target, value: u16) -> Result<(), &'static str> {
+  if value > limit(target) {
+    return Err("Value Out Of Range");
+  }
+
+  let old: u16 = read(chip8, target);
+
+  if let (Target::Memory(_), Some(journal)) = (target, chip8.journal.as_mut()) {
+    if old != value {
+      journal.record(Change {
+        target,
+        old,
+        new: value,
+      });
+    }
+  }
+
+  write(chip8, target, value);
+
+  Ok(())
+}
+
+// Writes bytes sent by a debugger to memory, recording them in the journal.
+pub(crate) fn edit_memory(
+  chip8: &mut Chip8,
+  address: usize,
+  bytes: &[u8],
+) -> Result<(), &'static str> {
+  if address + bytes.len() > chip8.memory.len() {
+    return Err("Invalid Memory Range");
+  }
+
+  for (offset, byte) in bytes.iter().enumerate() {
+    let target: Target = Target::Memory((address + offset) as u16);
+
+    edit(chip8, target, *byte as u16)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_step_back() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    // LD V3, 05 / LD I, 0x3F0 / LD [I], V3 / LD V3, 00 / CLS
+    chip8
+      .load_bytes(
+        &[0x63, 0x05, 0xA3, 0xF0, 0xF3, 0x55, 0x63, 0x00, 0x00, 0xE0],
+        false,
+      )
+      .unwrap();
+    chip8.journal(Some(Journal::new()));
+
+    for _ in 0..4 {
+      chip8.step();
+    }
+
+    let journal: &Journal = chip8.journal.as_ref().unwrap();
+    let (entry, change) = journal.query("[0x3F3]").unwrap().unwrap();
+
+    assert_eq!(entry.pc, 0x204);
+    assert_eq!(change.new, 0x05);
+    assert_eq!(journal.query("V3 == 0").unwrap().unwrap().0.pc, 0x206);
+    assert_eq!(journal.query("V3 = 5").unwrap().unwrap().0.pc, 0x200);
+    assert_eq!(journal.query("VF = 1").unwrap(), None);
+    assert!(journal.query("Q1").is_err());
+
+    assert!(chip8.step_back());
+    assert!(chip8.step_back());
+
+    assert_eq!(chip8.pc, 0x204);
+    assert_eq!(chip8.reg_v[3], 0x05);
+    assert_eq!(chip8.memory[0x3F3], 0x00);
+    assert_eq!(chip8.cycles, 2);
+
+    assert_eq!(chip8.step_forward(), Some(0xF355));
+    assert_eq!(chip8.memory[0x3F3], 0x05);
+    assert_eq!(chip8.pc, 0x206);
+
+    assert!(chip8.step_back());
+    assert!(chip8.step_back());
+    assert!(chip8.step_back());
+    assert!(!chip8.step_back());
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.reg_i, 0x000);
+  }
+
+  #[test]
+  fn test_step_back_memory_edit() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    // LD I, 0x3F0 / LD V0, [I] / LD I, 0x3F0 / LD V0, [I]
+    chip8
+      .load_bytes(&[0xA3, 0xF0, 0xF0, 0x65, 0xA3, 0xF0, 0xF0, 0x65], false)
+      .unwrap();
+    chip8.journal(Some(Journal::new()));
+    chip8.step();
+    chip8.step();
+
+    assert_eq!(edit(&mut chip8, Target::Memory(0x3F0), 0x07), Ok(()));
+    assert_eq!(edit_memory(&mut chip8, 0x3F1, &[0x08, 0x09]), Ok(()));
+    assert_eq!(
+      edit_memory(&mut chip8, 0xFFE, &[0x01, 0x02, 0x03]),
+      Err("Invalid Memory Range")
+    );
+
+    chip8.step();
+    chip8.step();
+
+    let journal: &Journal = chip8.journal.as_ref().unwrap();
+
+    assert_eq!(chip8.reg_v[0], 0x07);
+    assert_eq!(journal.query("[0x3F2]").unwrap().unwrap().0.pc, 0x202);
+
+    // Stepping back over the edits restores the bytes they replaced
+    assert!(chip8.step_back());
+    assert!(chip8.step_back());
+    assert!(chip8.step_back());
+
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(&chip8.memory[0x3F0..0x3F3], &[0x00, 0x00, 0x00]);
+
+    assert_eq!(chip8.step_forward(), Some(0xF065));
+    assert_eq!(&chip8.memory[0x3F0..0x3F3], &[0x07, 0x08, 0x09]);
+  }
+
+  #[test]
+  fn test_edit() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    assert_eq!(edit(&mut chip8, Target::Pc, 0xFFD), Ok(()));
+    assert_eq!(
+      edit(&mut chip8, Target::Pc, 0xFFE),
+      Err("Value Out Of Range")
+    );
+    assert_eq!(
+      edit(&mut chip8, Target::I, 0xFFF),
+      Err("Value Out Of Range")
+    );
+    assert_eq!(
+      edit(&mut chip8, Target::Sp, 0x10),
+      Err("Value Out Of Range")
+    );
+    assert_eq!(
+      edit(&mut chip8, Target::V(3), 0x100),
+      Err("Value Out Of Range")
+    );
+    assert_eq!(chip8.pc, 0xFFD);
+    assert_eq!(chip8.sp, 0x0);
+  }
+
+  #[test]
+  fn test_overflow() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    // CALL 0x200 - recurses until the stack is full
+    chip8.load_bytes(&[0x22, 0x00], false).unwrap();
+    chip8.journal(Some(Journal::new()));
+
+    for _ in 0..=STACK {
+      chip8.step();
+    }
+
+    assert!(chip8.overflow);
+    assert_eq!(chip8.sp as usize, STACK);
+    assert_eq!(chip8.pc, 0x202);
+
+    let journal: &Journal = chip8.journal.as_ref().unwrap();
+
+    assert_eq!(
+      journal.query("OVERFLOW = 1").unwrap().unwrap().0.cycle,
+      STACK as u64
+    );
+
+    assert!(chip8.step_back());
+    assert!(!chip8.overflow);
+    assert_eq!(chip8.pc, 0x200);
+  }
+
+  #[test]
+  fn test_touches_display() {
+    for opcode in [
+      0x00E0, 0x00B2, 0x00C4, 0x00FB, 0x00FC, 0x00FE, 0x00FF, 0xD125,
+    ] {
+      assert!(touches_display(opcode), "{:04X}", opcode);
+    }
+
+    for opcode in [0x00EE, 0x00FD, 0x0123, 0x6005, 0xF055] {
+      assert!(!touches_display(opcode), "{:04X}", opcode);
+    }
+  }
+}
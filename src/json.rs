@@ -0,0 +1,382 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FResult;
+use core::iter::Peekable;
+use core::str::Chars;
+
+// Minimal JSON document model used by the debug adapter and data files.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    let mut chars: Peekable<Chars> = input.chars().peekable();
+    let value: Self = parse_value(&mut chars)?;
+
+    skip_whitespace(&mut chars);
+
+    if chars.peek().is_some() {
+      Err("Trailing JSON Data")
+    } else {
+      Ok(value)
+    }
+  }
+
+  pub fn object<K: Into<String>>(fields: Vec<(K, Self)>) -> Self {
+    Self::Object(
+      fields
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect(),
+    )
+  }
+
+  pub fn get(&self, key: &str) -> Option<&Self> {
+    match self {
+      Self::Object(fields) => fields
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  // Looks up a nested value by a sequence of object keys.
+  pub fn path(&self, keys: &[&str]) -> Option<&Self> {
+    keys.iter().try_fold(self, |value, key| value.get(key))
+  }
+
+  pub fn set<K: Into<String>>(&mut self, key: K, value: Self) {
+    let key: String = key.into();
+
+    if let Self::Object(fields) = self {
+      match fields.iter_mut().find(|(name, _)| *name == key) {
+        Some(field) => field.1 = value,
+        None => fields.push((key, value)),
+      }
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Self::String(value) => Some(value),
+      _ => None,
+    }
+  }
+
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      Self::Bool(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      Self::Number(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  pub fn as_i64(&self) -> Option<i64> {
+    self
+      .as_f64()
+      .filter(|value| value.fract() == 0.0)
+      .map(|value| value as i64)
+  }
+
+  pub fn as_array(&self) -> Option<&[Self]> {
+    match self {
+      Self::Array(values) => Some(values),
+      _ => None,
+    }
+  }
+
+  pub fn as_object(&self) -> Option<&[(String, Self)]> {
+    match self {
+      Self::Object(fields) => Some(fields),
+      _ => None,
+    }
+  }
+}
+
+impl Display for Json {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    match self {
+      Self::Null => f.write_str("null"),
+      Self::Bool(value) => write!(f, "{}", value),
+      Self::Number(value) if value.is_finite() => write!(f, "{}", value),
+      Self::Number(_) => f.write_str("null"),
+      Self::String(value) => write_string(f, value),
+      Self::Array(values) => {
+        f.write_str("[")?;
+
+        for (index, value) in values.iter().enumerate() {
+          if index > 0 {
+            f.write_str(",")?;
+          }
+
+          write!(f, "{}", value)?;
+        }
+
+        f.write_str("]")
+      }
+      Self::Object(fields) => {
+        f.write_str("{")?;
+
+        for (index, (key, value)) in fields.iter().enumerate() {
+          if index > 0 {
+            f.write_str(",")?;
+          }
+
+          write_string(f, key)?;
+          write!(f, ":{}", value)?;
+        }
+
+        f.write_str("}")
+      }
+    }
+  }
+}
+
+impl From<bool> for Json {
+  fn from(value: bool) -> Self {
+    Self::Bool(value)
+  }
+}
+
+impl From<&str> for Json {
+  fn from(value: &str) -> Self {
+    Self::String(value.to_string())
+  }
+}
+
+impl From<String> for Json {
+  fn from(value: String) -> Self {
+    Self::String(value)
+  }
+}
+
+impl From<Vec<Json>> for Json {
+  fn from(values: Vec<Json>) -> Self {
+    Self::Array(values)
+  }
+}
+
+macro_rules! impl_from_number {
+  ($($ty:ty),*) => {
+    $(
+      impl From<$ty> for Json {
+        fn from(value: $ty) -> Self {
+          Self::Number(value as f64)
+        }
+      }
+    )*
+  };
+}
+
+impl_from_number!(u8, u16, u32, u64, usize, i32, i64, f64);
+
+fn write_string(f: &mut Formatter, value: &str) -> FResult {
+  f.write_str("\"")?;
+
+  for char in value.chars() {
+    match char {
+      '"' => f.write_str("\\\"")?,
+      '\\' => f.write_str("\\\\")?,
+      '\n' => f.write_str("\\n")?,
+      '\r' => f.write_str("\\r")?,
+      '\t' => f.write_str("\\t")?,
+      char if (char as u32) < 0x20 => write!(f, "\\u{:04x}", char as u32)?,
+      char => write!(f, "{}", char)?,
+    }
+  }
+
+  f.write_str("\"")
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+  while chars.peek().is_some_and(|char| char.is_whitespace()) {
+    chars.next();
+  }
+}
+
+fn expect(chars: &mut Peekable<Chars>, word: &str) -> Result<(), &'static str> {
+  for expected in word.chars() {
+    if chars.next() != Some(expected) {
+      return Err("Invalid JSON Literal");
+    }
+  }
+
+  Ok(())
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Json, &'static str> {
+  skip_whitespace(chars);
+
+  match chars.peek() {
+    Some('n') => expect(chars, "null").map(|_| Json::Null),
+    Some('t') => expect(chars, "true").map(|_| Json::Bool(true)),
+    Some('f') => expect(chars, "false").map(|_| Json::Bool(false)),
+    Some('"') => parse_string(chars).map(Json::String),
+    Some('[') => parse_array(chars),
+    Some('{') => parse_object(chars),
+    Some(_) => parse_number(chars),
+    None => Err("Unexpected End Of JSON"),
+  }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Json, &'static str> {
+  let mut number: String = String::new();
+
+  while let Some(&char) = chars.peek() {
+    if char.is_ascii_digit() || "+-.eE".contains(char) {
+      number.push(char);
+      chars.next();
+    } else {
+      break;
+    }
+  }
+
+  number
+    .parse()
+    .map(Json::Number)
+    .map_err(|_| "Invalid JSON Number")
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, &'static str> {
+  let mut string: String = String::new();
+
+  chars.next(); // opening quote
+
+  loop {
+    match chars.next().ok_or("Unterminated JSON String")? {
+      '"' => return Ok(string),
+      '\\' => match chars.next().ok_or("Unterminated JSON String")? {
+        'b' => string.push('\u{8}'),
+        'f' => string.push('\u{c}'),
+        'n' => string.push('\n'),
+        'r' => string.push('\r'),
+        't' => string.push('\t'),
+        'u' => {
+          let code: String = chars.by_ref().take(4).collect();
+          let code: u32 = u32::from_str_radix(&code, 16).map_err(|_| "Invalid JSON Escape")?;
+
+          string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        char => string.push(char),
+      },
+      char => string.push(char),
+    }
+  }
+}
+
+fn parse_array(chars: &mut Peekable<Chars>) -> Result<Json, &'static str> {
+  let mut values: Vec<Json> = Vec::new();
+
+  chars.next(); // opening bracket
+  skip_whitespace(chars);
+
+  if chars.peek() == Some(&']') {
+    chars.next();
+    return Ok(Json::Array(values));
+  }
+
+  loop {
+    values.push(parse_value(chars)?);
+    skip_whitespace(chars);
+
+    match chars.next() {
+      Some(',') => continue,
+      Some(']') => return Ok(Json::Array(values)),
+      _ => return Err("Invalid JSON Array"),
+    }
+  }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>) -> Result<Json, &'static str> {
+  let mut fields: Vec<(String, Json)> = Vec::new();
+
+  chars.next(); // opening brace
+  skip_whitespace(chars);
+
+  if chars.peek() == Some(&'}') {
+    chars.next();
+    return Ok(Json::Object(fields));
+  }
+
+  loop {
+    skip_whitespace(chars);
+
+    if chars.peek() != Some(&'"') {
+      return Err("Invalid JSON Key");
+    }
+
+    let key: String = parse_string(chars)?;
+
+    skip_whitespace(chars);
+
+    if chars.next() != Some(':') {
+      return Err("Invalid JSON Object");
+    }
+
+    fields.push((key, parse_value(chars)?));
+    skip_whitespace(chars);
+
+    match chars.next() {
+      Some(',') => continue,
+      Some('}') => return Ok(Json::Object(fields)),
+      _ => return Err("Invalid JSON Object"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let json: Json =
+      Json::parse(r#"{"a": [1, 2.5, -3e2], "b": {"c": "x\"yA"}, "d": null, "e": true}"#).unwrap();
+
+    assert_eq!(
+      json
+        .get("a")
+        .and_then(Json::as_array)
+        .map(|array| array.len()),
+      Some(3)
+    );
+    assert_eq!(json.path(&["b", "c"]).and_then(Json::as_str), Some("x\"yA"));
+    assert_eq!(json.get("d"), Some(&Json::Null));
+    assert_eq!(json.get("e").and_then(Json::as_bool), Some(true));
+    assert!(Json::parse("{\"a\": }").is_err());
+    assert!(Json::parse("[1, 2] 3").is_err());
+  }
+
+  #[test]
+  fn test_display() {
+    let mut json: Json = Json::object(vec![
+      ("seq", Json::from(1u64)),
+      ("text", Json::from("a\n\"b\"")),
+    ]);
+
+    json.set(
+      "list",
+      Json::from(vec![Json::Null, Json::from(false), Json::from(0.5)]),
+    );
+
+    assert_eq!(
+      json.to_string(),
+      r#"{"seq":1,"text":"a\n\"b\"","list":[null,false,0.5]}"#
+    );
+    assert_eq!(Json::parse(&json.to_string()), Ok(json));
+  }
+}
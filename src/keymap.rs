@@ -0,0 +1,403 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FResult;
+use std::collections::BTreeMap;
+
+use crate::ini::Ini;
+use crate::sdl2::SDL_Scancode;
+
+// Physical keys by name, as scancodes of their position on a US keyboard.
+const NAMES: [(&str, u32); 37] = [
+  ("return", SDL_Scancode::SDL_SCANCODE_RETURN as u32),
+  ("escape", SDL_Scancode::SDL_SCANCODE_ESCAPE as u32),
+  ("backspace", SDL_Scancode::SDL_SCANCODE_BACKSPACE as u32),
+  ("tab", SDL_Scancode::SDL_SCANCODE_TAB as u32),
+  ("space", SDL_Scancode::SDL_SCANCODE_SPACE as u32),
+  ("f1", SDL_Scancode::SDL_SCANCODE_F1 as u32),
+  ("f2", SDL_Scancode::SDL_SCANCODE_F2 as u32),
+  ("f3", SDL_Scancode::SDL_SCANCODE_F3 as u32),
+  ("f4", SDL_Scancode::SDL_SCANCODE_F4 as u32),
+  ("f5", SDL_Scancode::SDL_SCANCODE_F5 as u32),
+  ("f6", SDL_Scancode::SDL_SCANCODE_F6 as u32),
+  ("f7", SDL_Scancode::SDL_SCANCODE_F7 as u32),
+  ("f8", SDL_Scancode::SDL_SCANCODE_F8 as u32),
+  ("f9", SDL_Scancode::SDL_SCANCODE_F9 as u32),
+  ("f10", SDL_Scancode::SDL_SCANCODE_F10 as u32),
+  ("f11", SDL_Scancode::SDL_SCANCODE_F11 as u32),
+  ("f12", SDL_Scancode::SDL_SCANCODE_F12 as u32),
+  ("home", SDL_Scancode::SDL_SCANCODE_HOME as u32),
+  ("pageup", SDL_Scancode::SDL_SCANCODE_PAGEUP as u32),
+  ("delete", SDL_Scancode::SDL_SCANCODE_DELETE as u32),
+  ("end", SDL_Scancode::SDL_SCANCODE_END as u32),
+  ("pagedown", SDL_Scancode::SDL_SCANCODE_PAGEDOWN as u32),
+  ("right", SDL_Scancode::SDL_SCANCODE_RIGHT as u32),
+  ("left", SDL_Scancode::SDL_SCANCODE_LEFT as u32),
+  ("down", SDL_Scancode::SDL_SCANCODE_DOWN as u32),
+  ("up", SDL_Scancode::SDL_SCANCODE_UP as u32),
+  ("kpenter", SDL_Scancode::SDL_SCANCODE_KP_ENTER as u32),
+  ("kp1", SDL_Scancode::SDL_SCANCODE_KP_1 as u32),
+  ("kp2", SDL_Scancode::SDL_SCANCODE_KP_2 as u32),
+  ("kp3", SDL_Scancode::SDL_SCANCODE_KP_3 as u32),
+  ("kp4", SDL_Scancode::SDL_SCANCODE_KP_4 as u32),
+  ("kp5", SDL_Scancode::SDL_SCANCODE_KP_5 as u32),
+  ("kp6", SDL_Scancode::SDL_SCANCODE_KP_6 as u32),
+  ("kp7", SDL_Scancode::SDL_SCANCODE_KP_7 as u32),
+  ("kp8", SDL_Scancode::SDL_SCANCODE_KP_8 as u32),
+  ("kp9", SDL_Scancode::SDL_SCANCODE_KP_9 as u32),
+  ("kp0", SDL_Scancode::SDL_SCANCODE_KP_0 as u32),
+];
+
+// Punctuation keys in scancode order, starting at 45.
+const PUNCTUATION: [(u8, u32); 12] = [
+  (b'-', SDL_Scancode::SDL_SCANCODE_MINUS as u32),
+  (b'=', SDL_Scancode::SDL_SCANCODE_EQUALS as u32),
+  (b'[', SDL_Scancode::SDL_SCANCODE_LEFTBRACKET as u32),
+  (b']', SDL_Scancode::SDL_SCANCODE_RIGHTBRACKET as u32),
+  (b'\\', SDL_Scancode::SDL_SCANCODE_BACKSLASH as u32),
+  (b'#', SDL_Scancode::SDL_SCANCODE_NONUSHASH as u32),
+  (b';', SDL_Scancode::SDL_SCANCODE_SEMICOLON as u32),
+  (b'\'', SDL_Scancode::SDL_SCANCODE_APOSTROPHE as u32),
+  (b'`', SDL_Scancode::SDL_SCANCODE_GRAVE as u32),
+  (b',', SDL_Scancode::SDL_SCANCODE_COMMA as u32),
+  (b'.', SDL_Scancode::SDL_SCANCODE_PERIOD as u32),
+  (b'/', SDL_Scancode::SDL_SCANCODE_SLASH as u32),
+];
+
+// Game controller buttons by name, in SDL button order.
+const BUTTONS: [&str; 15] = [
+  "pad-a",
+  "pad-b",
+  "pad-x",
+  "pad-y",
+  "pad-back",
+  "pad-guide",
+  "pad-start",
+  "pad-ls",
+  "pad-rs",
+  "pad-lb",
+  "pad-rb",
+  "pad-up",
+  "pad-down",
+  "pad-left",
+  "pad-right",
+];
+
+// Game controller axis directions by name, in SDL axis order.
+const AXES: [(&str, &str); 6] = [
+  ("pad-lx-", "pad-lx+"),
+  ("pad-ly-", "pad-ly+"),
+  ("pad-rx-", "pad-rx+"),
+  ("pad-ry-", "pad-ry+"),
+  ("", "pad-lt"),
+  ("", "pad-rt"),
+];
+
+// Emulator hotkeys and their names in keymap files.
+const ACTIONS: [(&str, Action); 16] = [
+  ("quit", Action::Quit),
+  ("pause", Action::Pause),
+  ("faster", Action::Faster),
+  ("slower", Action::Slower),
+  ("view", Action::View),
+  ("overlay", Action::Overlay),
+  ("step-back", Action::StepBack),
+  ("step-forward", Action::StepForward),
+  ("sprites-up", Action::SpritesUp),
+  ("sprites-down", Action::SpritesDown),
+  ("sprites-follow", Action::SpritesFollow),
+  ("sprites-start", Action::SpritesStart),
+  ("palette", Action::Palette),
+  ("layout", Action::Layout),
+  ("fullscreen", Action::Fullscreen),
+  ("browse", Action::Browse),
+];
+
+const DEFAULT: &str = "
+0 = x, pad-b
+1 = 1
+2 = 2, pad-up, pad-ly-
+3 = 3
+4 = q, pad-left, pad-lx-
+5 = w, pad-a
+6 = e, pad-right, pad-lx+
+7 = a
+8 = s, pad-down, pad-ly+
+9 = d
+a = z, pad-x
+b = c, pad-y
+c = 4
+d = r
+e = f
+f = v
+quit = escape
+pause = space, pad-start
+faster = up
+slower = down
+view = tab
+overlay = o
+step-back = left
+step-forward = right
+sprites-up = pageup
+sprites-down = pagedown
+sprites-follow = home
+sprites-start = end
+palette = p
+layout = l
+fullscreen = f11
+browse = b, pad-back
+";
+
+// A host key, controller button or direction of a controller axis.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Input {
+  Key(u32),       // scancode
+  Button(u8),     // SDL_GameControllerButton
+  Axis(u8, bool), // SDL_GameControllerAxis and whether it points to positive values
+}
+
+impl Input {
+  // Parses a key name (see `scancode`) or a controller input, ie. `pad-a` or `pad-lx+`.
+  pub fn parse(name: &str) -> Result<Self, &'static str> {
+    let name: String = name.trim().to_ascii_lowercase();
+
+    if let Some(index) = BUTTONS.iter().position(|button| *button == name) {
+      return Ok(Self::Button(index as u8));
+    }
+
+    for (index, (negative, positive)) in AXES.iter().enumerate() {
+      if *negative == name || *positive == name {
+        return Ok(Self::Axis(index as u8, *positive == name));
+      }
+    }
+
+    scancode(&name).map(Self::Key)
+  }
+}
+
+// What a host input does - press a CHIP-8 key or trigger an emulator hotkey.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+  Key(u8),
+  Quit,
+  Pause,
+  Faster,
+  Slower,
+  View,
+  Overlay,
+  StepBack,
+  StepForward,
+  SpritesUp,
+  SpritesDown,
+  SpritesFollow,
+  SpritesStart,
+  Palette,
+  Layout,
+  Fullscreen,
+  Browse,
+}
+
+impl Action {
+  // Parses a hex digit (`5`, `a`) or a hotkey name (`pause`).
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    let input: String = input.trim().to_ascii_lowercase();
+
+    if input.len() == 1 {
+      if let Ok(key) = u8::from_str_radix(&input, 16) {
+        return Ok(Self::Key(key));
+      }
+    }
+
+    ACTIONS
+      .iter()
+      .find(|(name, _)| *name == input)
+      .map(|(_, action)| *action)
+      .ok_or("Invalid Keymap Action")
+  }
+}
+
+impl Display for Action {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    match self {
+      Self::Key(key) => write!(f, "{:X}", key),
+      _ => match ACTIONS.iter().find(|(_, action)| action == self) {
+        Some((name, _)) => f.write_str(name),
+        None => write!(f, "{:?}", self),
+      },
+    }
+  }
+}
+
+// Binds host keys and controller inputs to CHIP-8 keys and emulator hotkeys.
+//
+// Keymap files use the `[keymap]` section of an INI file, one action per line
+// followed by any number of host inputs (`5 = w, up, pad-a`). Keys are named
+// after their position on a US keyboard, so the default 1234/QWER/ASDF/ZXCV
+// grid keeps its shape on AZERTY or Dvorak layouts. Binding an action replaces
+// all of its previous host keys. A `[keymap.NAME]` section overrides the keymap
+// for the ROM whose file name (without extension) is NAME.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Keymap {
+  bindings: BTreeMap<Input, Action>,
+}
+
+impl Keymap {
+  pub fn new() -> Self {
+    let mut this: Self = Self {
+      bindings: BTreeMap::new(),
+    };
+
+    if let Ok(ini) = Ini::parse(DEFAULT) {
+      let _ = this.apply(&ini, "");
+    }
+
+    this
+  }
+
+  #[inline]
+  pub fn get(&self, input: Input) -> Option<Action> {
+    self.bindings.get(&input).copied()
+  }
+
+  // Returns the host inputs bound to an action.
+  pub fn inputs(&self, action: Action) -> Vec<Input> {
+    self
+      .bindings
+      .iter()
+      .filter(|(_, bound)| **bound == action)
+      .map(|(input, _)| *input)
+      .collect()
+  }
+
+  // Replaces the host inputs of an action.
+  pub fn bind(&mut self, action: Action, inputs: &[Input]) {
+    self.bindings.retain(|_, bound| *bound != action);
+
+    for input in inputs {
+      self.bindings.insert(*input, action);
+    }
+  }
+
+  // Binds a single host input, keeping the other inputs of its action.
+  pub fn set(&mut self, input: Input, action: Action) {
+    self.bindings.insert(input, action);
+  }
+
+  // Parses a single binding, ie. `5=w,up`.
+  pub fn bind_str(&mut self, input: &str) -> Result<(), &'static str> {
+    match input.find('=') {
+      Some(index) => self.bind_entry(&input[..index], &input[index + 1..]),
+      None => Err("Invalid Keymap Binding"),
+    }
+  }
+
+  // Applies the `[keymap]` section and the `[keymap.ROM]` override of a file.
+  pub fn apply_file(&mut self, ini: &Ini, rom: &str) -> Result<(), &'static str> {
+    self.apply(ini, "keymap")?;
+    self.apply(ini, &format!("keymap.{}", rom))
+  }
+
+  pub fn apply(&mut self, ini: &Ini, section: &str) -> Result<(), &'static str> {
+    for (action, keys) in ini.section(section) {
+      self.bind_entry(action, keys)?;
+    }
+
+    Ok(())
+  }
+
+  fn bind_entry(&mut self, action: &str, inputs: &str) -> Result<(), &'static str> {
+    let action: Action = Action::parse(action)?;
+    let inputs: Vec<Input> = inputs
+      .split(',')
+      .map(str::trim)
+      .filter(|input| !input.is_empty())
+      .map(Input::parse)
+      .collect::<Result<_, _>>()?;
+
+    self.bind(action, &inputs);
+
+    Ok(())
+  }
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Parses a host key name - a letter, digit or punctuation key or one of `NAMES`.
+pub fn scancode(name: &str) -> Result<u32, &'static str> {
+  let name: String = name.to_ascii_lowercase();
+
+  match name.as_bytes() {
+    [byte @ b'a'..=b'z'] => return Ok((byte - b'a') as u32 + SDL_Scancode::SDL_SCANCODE_A as u32),
+    [b'0'] => return Ok(SDL_Scancode::SDL_SCANCODE_0 as u32),
+    [byte @ b'1'..=b'9'] => return Ok((byte - b'1') as u32 + SDL_Scancode::SDL_SCANCODE_1 as u32),
+    [byte] => {
+      if let Some((_, scancode)) = PUNCTUATION.iter().find(|(char, _)| char == byte) {
+        return Ok(*scancode);
+      }
+    }
+    _ => {}
+  }
+
+  NAMES
+    .iter()
+    .find(|(key, _)| *key == name)
+    .map(|(_, scancode)| *scancode)
+    .ok_or("Invalid Keymap Key")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(scancode: SDL_Scancode) -> Input {
+    Input::Key(scancode as u32)
+  }
+
+  #[test]
+  fn test_keymap() {
+    let mut keymap: Keymap = Keymap::new();
+    let ini: Ini =
+      Ini::parse("[keymap]\n1 = w, up\npause = p\n[keymap.PONG]\nc = kp1, pad-ry-\n").unwrap();
+
+    assert_eq!(
+      keymap.get(key(SDL_Scancode::SDL_SCANCODE_X)),
+      Some(Action::Key(0x0))
+    );
+    assert_eq!(
+      keymap.get(key(SDL_Scancode::SDL_SCANCODE_ESCAPE)),
+      Some(Action::Quit)
+    );
+    assert_eq!(keymap.get(Input::Button(11)), Some(Action::Key(0x2)));
+
+    keymap.apply_file(&ini, "PONG").unwrap();
+
+    assert_eq!(
+      keymap.get(key(SDL_Scancode::SDL_SCANCODE_W)),
+      Some(Action::Key(0x1))
+    );
+    assert_eq!(
+      keymap.get(key(SDL_Scancode::SDL_SCANCODE_UP)),
+      Some(Action::Key(0x1))
+    );
+    assert_eq!(keymap.get(key(SDL_Scancode::SDL_SCANCODE_1)), None);
+    assert_eq!(keymap.get(key(SDL_Scancode::SDL_SCANCODE_SPACE)), None);
+    assert_eq!(
+      keymap.inputs(Action::Pause),
+      vec![key(SDL_Scancode::SDL_SCANCODE_P)]
+    );
+    assert_eq!(
+      keymap.inputs(Action::Key(0xC)),
+      vec![key(SDL_Scancode::SDL_SCANCODE_KP_1), Input::Axis(3, false)]
+    );
+    assert_eq!(Input::parse("pad-lt"), Ok(Input::Axis(4, true)));
+    assert_eq!(scancode("0"), Ok(SDL_Scancode::SDL_SCANCODE_0 as u32));
+    assert_eq!(scancode("/"), Ok(SDL_Scancode::SDL_SCANCODE_SLASH as u32));
+    assert_eq!(keymap.bind_str("sprites-up=,"), Ok(()));
+    assert_eq!(keymap.bind_str("g=w"), Err("Invalid Keymap Action"));
+    assert_eq!(keymap.bind_str("5=shift"), Err("Invalid Keymap Key"));
+    assert_eq!(Action::StepBack.to_string(), "step-back");
+  }
+}
@@ -12,13 +12,76 @@ extern crate libc;
 #[macro_use]
 mod macros;
 
+mod afterimage;
+mod browser;
 mod chip8;
+mod config;
+mod coverage;
+mod dap;
+mod database;
+mod gdb;
+mod ini;
 mod instruction;
+mod journal;
+mod keymap;
+mod json;
+mod number;
+mod palette;
+mod profiler;
 mod runner;
+mod scaler;
 mod sdl2;
+mod sha1;
+mod symbols;
+mod trace;
 
+pub use self::afterimage::Afterimage;
+pub use self::afterimage::Persistence;
+pub use self::browser::Browser;
+pub use self::browser::Rom;
 pub use self::chip8::Chip8;
+pub use self::chip8::Draw;
+pub use self::chip8::DrawCall;
 pub use self::chip8::Mode;
+pub use self::chip8::Quirks;
+pub use self::config::parse_bool;
+pub use self::config::parse_scale;
+pub use self::config::Config;
+pub use self::config::Layout;
+pub use self::config::Overrides;
+pub use self::coverage::Access;
+pub use self::coverage::Coverage;
+pub use self::dap::DapServer;
+pub use self::database::detect;
+pub use self::database::Platform;
+pub use self::database::Program;
+pub use self::gdb::GdbState;
+pub use self::gdb::GdbStub;
+pub use self::ini::Ini;
 pub use self::instruction::Instruction;
+pub use self::journal::Change;
+pub use self::journal::Entry;
+pub use self::journal::Journal;
+pub use self::journal::Target;
+pub use self::json::Json;
+pub use self::keymap::Action;
+pub use self::keymap::Input;
+pub use self::keymap::Keymap;
+pub use self::number::parse_hex;
+pub use self::number::parse_number;
+pub use self::palette::Color;
+pub use self::palette::Palette;
+pub use self::profiler::Profiler;
+pub use self::profiler::Routine;
 pub use self::runner::ChipRunner;
+pub use self::scaler::Filter;
 pub use self::sdl2::*;
+pub use self::sha1::sha1;
+pub use self::symbols::Monitor;
+pub use self::symbols::Symbols;
+pub use self::trace::TraceDiff;
+pub use self::trace::TraceField;
+pub use self::trace::TraceMap;
+pub use self::trace::TracePair;
+pub use self::trace::TraceRecord;
+pub use self::trace::Tracer;
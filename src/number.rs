@@ -0,0 +1,43 @@
+// Parses a number typed by the user - hexadecimal with a `0x` prefix, or else decimal.
+pub fn parse_number(input: &str) -> Option<u16> {
+  match strip_hex_prefix(input) {
+    Some(digits) => u16::from_str_radix(digits, 16).ok(),
+    None => input.parse().ok(),
+  }
+}
+
+// Parses a number that is always hexadecimal, with or without a `0x` prefix.
+pub fn parse_hex(input: &str) -> Option<u16> {
+  u16::from_str_radix(strip_hex_prefix(input).unwrap_or(input), 16).ok()
+}
+
+fn strip_hex_prefix(input: &str) -> Option<&str> {
+  input
+    .strip_prefix("0x")
+    .or_else(|| input.strip_prefix("0X"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_number() {
+    assert_eq!(parse_number("0x200"), Some(0x200));
+    assert_eq!(parse_number("0XFFE"), Some(0xFFE));
+    assert_eq!(parse_number("512"), Some(512));
+    assert_eq!(parse_number("200h"), None);
+    assert_eq!(parse_number("0x"), None);
+    assert_eq!(parse_number("0x10000"), None);
+  }
+
+  #[test]
+  fn test_parse_hex() {
+    assert_eq!(parse_hex("0x200"), Some(0x200));
+    assert_eq!(parse_hex("0Xff"), Some(0xFF));
+    assert_eq!(parse_hex("a22a"), Some(0xA22A));
+    assert_eq!(parse_hex("512"), Some(0x512));
+    assert_eq!(parse_hex("-1"), None);
+    assert_eq!(parse_hex(""), None);
+  }
+}
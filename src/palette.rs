@@ -0,0 +1,187 @@
+pub type Color = (u8, u8, u8);
+
+// Built-in palettes by name, in the order the palette hotkey cycles them.
+pub const THEMES: [(&str, Palette); 6] = [
+  (
+    "classic",
+    Palette {
+      foreground: (0xFF, 0xFF, 0xFF),
+      background: (0x00, 0x00, 0x00),
+      border: (0x80, 0x80, 0x80),
+      foreground2: (0xAA, 0xAA, 0xAA),
+      blend: (0x55, 0x55, 0x55),
+    },
+  ),
+  (
+    "green",
+    Palette {
+      foreground: (0x33, 0xFF, 0x66),
+      background: (0x0A, 0x1A, 0x0F),
+      border: (0x1E, 0x3A, 0x25),
+      foreground2: (0x1F, 0x9E, 0x40),
+      blend: (0x99, 0xFF, 0xB3),
+    },
+  ),
+  (
+    "amber",
+    Palette {
+      foreground: (0xFF, 0xB0, 0x00),
+      background: (0x1A, 0x10, 0x00),
+      border: (0x4D, 0x35, 0x00),
+      foreground2: (0xB3, 0x7B, 0x00),
+      blend: (0xFF, 0xD7, 0x80),
+    },
+  ),
+  (
+    "lcd",
+    Palette {
+      foreground: (0x2E, 0x35, 0x27),
+      background: (0xC6, 0xD0, 0xB4),
+      border: (0x8A, 0x94, 0x77),
+      foreground2: (0x6B, 0x75, 0x60),
+      blend: (0x12, 0x18, 0x10),
+    },
+  ),
+  (
+    "octo",
+    Palette {
+      foreground: (0xFF, 0xCC, 0x00),
+      background: (0x99, 0x66, 0x00),
+      border: (0x00, 0x00, 0x00),
+      foreground2: (0xFF, 0x66, 0x00),
+      blend: (0x66, 0x22, 0x00),
+    },
+  ),
+  (
+    "gameboy",
+    Palette {
+      foreground: (0x0F, 0x38, 0x0F),
+      background: (0x9B, 0xBC, 0x0F),
+      border: (0x30, 0x62, 0x30),
+      foreground2: (0x8B, 0xAC, 0x0F),
+      blend: (0x30, 0x62, 0x30),
+    },
+  ),
+];
+
+// Colors of the game display.
+//
+// Multi-plane modes color a pixel by the planes it is lit in - background for
+// none, foreground for the first, foreground2 for the second and blend for both.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Palette {
+  pub foreground: Color,  // lit pixels
+  pub background: Color,  // unlit pixels
+  pub border: Color,      // frame around the display
+  pub foreground2: Color, // pixels lit in the second plane only
+  pub blend: Color,       // pixels lit in both planes
+}
+
+impl Palette {
+  pub const fn new() -> Self {
+    THEMES[0].1
+  }
+
+  pub fn theme(name: &str) -> Result<Self, &'static str> {
+    THEMES
+      .iter()
+      .find(|(theme, _)| theme.eq_ignore_ascii_case(name.trim()))
+      .map(|(_, palette)| *palette)
+      .ok_or("Invalid Theme")
+  }
+
+  // Returns the name of the built-in palette with these colors.
+  pub fn name(&self) -> Option<&'static str> {
+    THEMES
+      .iter()
+      .find(|(_, palette)| palette == self)
+      .map(|(name, _)| *name)
+  }
+
+  // Returns the built-in palette after this one - the first for custom colors.
+  pub fn next(&self) -> Self {
+    match THEMES.iter().position(|(_, palette)| palette == self) {
+      Some(index) => THEMES[(index + 1) % THEMES.len()].1,
+      None => THEMES[0].1,
+    }
+  }
+
+  // Returns the color of a pixel from a bit mask of the planes it is lit in.
+  pub fn pixel(&self, planes: u8) -> Color {
+    match planes & 0x3 {
+      0 => self.background,
+      1 => self.foreground,
+      2 => self.foreground2,
+      _ => self.blend,
+    }
+  }
+}
+
+impl Default for Palette {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Blends `b` over `a` by `amount` out of 255.
+pub fn mix(a: Color, b: Color, amount: u8) -> Color {
+  let channel = |a: u8, b: u8| -> u8 {
+    ((a as u16 * (255 - amount) as u16 + b as u16 * amount as u16) / 255) as u8
+  };
+
+  (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+// Parses an RGB color, ie. `#FFB000`.
+pub fn parse_color(input: &str) -> Result<Color, &'static str> {
+  let input: &str = input.trim().trim_start_matches('#');
+
+  if input.len() != 6 {
+    return Err("Invalid Color");
+  }
+
+  let value: u32 = u32::from_str_radix(input, 16).map_err(|_| "Invalid Color")?;
+
+  Ok(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_theme_lookup() {
+    let amber: Palette = Palette::theme("Amber").unwrap();
+
+    assert_eq!(amber.name(), Some("amber"));
+    assert_eq!(amber.pixel(0), (0x1A, 0x10, 0x00));
+    assert_eq!(amber.pixel(3), amber.blend);
+    assert_eq!(Palette::theme("sepia"), Err("Invalid Theme"));
+  }
+
+  #[test]
+  fn test_theme_cycle() {
+    assert_eq!(
+      Palette::theme("amber").unwrap().next(),
+      Palette::theme("lcd").unwrap()
+    );
+    assert_eq!(Palette::theme("gameboy").unwrap().next(), Palette::new());
+  }
+
+  #[test]
+  fn test_custom_palette() {
+    let custom: Palette = Palette {
+      foreground: (1, 2, 3),
+      ..Palette::new()
+    };
+
+    assert_eq!(custom.name(), None);
+    assert_eq!(custom.next(), Palette::new());
+  }
+
+  #[test]
+  fn test_parse_color() {
+    assert_eq!(parse_color("#FFB000"), Ok((0xFF, 0xB0, 0x00)));
+    assert_eq!(parse_color("FFB0"), Err("Invalid Color"));
+  }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::io::Result as IResult;
+use std::io::Write;
+
+use crate::chip8::Chip8;
+use crate::instruction::Instruction;
+use crate::symbols::Symbols;
+
+const ADDRESSES: usize = 0x1000;
+const HOT_SPOTS: usize = 0x20;
+const UNKNOWN: u16 = 0xFFFF; // histogram key of opcodes without an instruction
+
+// Instruction counts of a single subroutine.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Routine {
+  pub calls: u64,     // number of times the subroutine was entered
+  pub inclusive: u64, // instructions executed from entry to return, callees included
+  pub exclusive: u64, // instructions executed by the subroutine itself
+}
+
+// Collects execution statistics while the interpreter runs.
+//
+// Subroutines are tracked on a shadow call stack driven by changes of `sp`
+// across CALL/RET. Instructions outside of any subroutine are attributed to
+// the address execution started at. Recursive calls count towards the
+// inclusive total of every active frame of the subroutine.
+pub struct Profiler {
+  total: u64,                       // instructions executed while profiling
+  frames: u64,                      // frames rendered while profiling
+  counts: Vec<u64>,                 // execution count per address
+  opcodes: Vec<u16>,                // last opcode executed per address
+  classes: BTreeMap<u16, u64>,      // execution count per instruction class
+  routines: BTreeMap<u16, Routine>, // counts per subroutine entry point
+  stack: Vec<(u16, u64)>,           // active subroutines and the total at entry
+  root: Option<u16>,                // address profiling started at
+  waits: BTreeMap<u16, u64>,        // frames spent per Fx0A wait address
+}
+
+impl Profiler {
+  pub fn new() -> Self {
+    Self {
+      total: 0,
+      frames: 0,
+      counts: vec![0; ADDRESSES],
+      opcodes: vec![0; ADDRESSES],
+      classes: BTreeMap::new(),
+      routines: BTreeMap::new(),
+      stack: Vec::new(),
+      root: None,
+      waits: BTreeMap::new(),
+    }
+  }
+
+  // Records an executed instruction - `pc` and `sp` are the values before execution.
+  pub(crate) fn record(&mut self, chip8: &Chip8, pc: u16, sp: u8, opcode: u16) {
+    let address: usize = pc as usize % ADDRESSES;
+    let class: u16 = Instruction::find(opcode).map_or(UNKNOWN, |instruction| instruction.code);
+    let current: u16 = self.current(pc);
+
+    self.total += 1;
+    self.counts[address] += 1;
+    self.opcodes[address] = opcode;
+
+    *self.classes.entry(class).or_default() += 1;
+
+    self.routines.entry(current).or_default().exclusive += 1;
+
+    if chip8.sp > sp {
+      self.routines.entry(chip8.pc).or_default().calls += 1;
+      self.stack.push((chip8.pc, self.total));
+    } else if chip8.sp < sp {
+      if let Some((entry, start)) = self.stack.pop() {
+        self.routines.entry(entry).or_default().inclusive += self.total - start;
+      }
+    }
+  }
+
+  // Records a rendered frame - `wait` is the address of a pending Fx0A.
+  pub(crate) fn frame(&mut self, wait: Option<u16>) {
+    self.frames += 1;
+
+    if let Some(address) = wait {
+      *self.waits.entry(address).or_default() += 1;
+    }
+  }
+
+  #[inline]
+  pub fn total(&self) -> u64 {
+    self.total
+  }
+
+  #[inline]
+  pub fn count(&self, address: u16) -> u64 {
+    self.counts[address as usize % ADDRESSES]
+  }
+
+  // Returns the counts of every subroutine, including frames that are still active.
+  pub fn routines(&self) -> BTreeMap<u16, Routine> {
+    let mut routines: BTreeMap<u16, Routine> = self.routines.clone();
+
+    for (entry, start) in self.stack.iter() {
+      routines.entry(*entry).or_default().inclusive += self.total - start;
+    }
+
+    if let Some(root) = self.root {
+      routines.entry(root).or_default().inclusive = self.total;
+    }
+
+    routines
+  }
+
+  pub fn report<W: Write>(&self, output: &mut W, symbols: &Symbols) -> IResult<()> {
+    let frames: u64 = self.frames.max(1);
+    let waits: u64 = self.waits.values().sum();
+
+    writeln!(
+      output,
+      "Profile: {} instructions, {} frames ({} waiting on Fx0A), {} instructions/frame",
+      self.total,
+      self.frames,
+      waits,
+      self.total / frames,
+    )?;
+
+    let mut hot: Vec<usize> = (0..ADDRESSES)
+      .filter(|index| self.counts[*index] > 0)
+      .collect();
+
+    hot.sort_by(|a, b| self.counts[*b].cmp(&self.counts[*a]).then(a.cmp(b)));
+
+    writeln!(output)?;
+    writeln!(output, "Hot spots")?;
+    writeln!(
+      output,
+      "  {:<20} {:>10} {:>7}  INSTRUCTION",
+      "ADDRESS", "COUNT", "%"
+    )?;
+
+    for index in hot.into_iter().take(HOT_SPOTS) {
+      let opcode: u16 = self.opcodes[index];
+      let text: String = match Instruction::find(opcode) {
+        Some(instruction) => symbols.disassemble(instruction, opcode),
+        None => format!("{:04X}", opcode),
+      };
+
+      writeln!(
+        output,
+        "  {:<20} {:>10} {:>6.2}%  {}",
+        symbols.locate(index as u16),
+        self.counts[index],
+        self.percent(self.counts[index]),
+        text,
+      )?;
+    }
+
+    let mut classes: Vec<(&u16, &u64)> = self.classes.iter().collect();
+
+    classes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    writeln!(output)?;
+    writeln!(output, "Instruction classes")?;
+    writeln!(output, "  {:<20} {:>10} {:>7}", "CLASS", "COUNT", "%")?;
+
+    for (class, count) in classes {
+      let name: String = match Instruction::find(*class) {
+        Some(instruction) if *class != UNKNOWN => {
+          format!("{} {}", instruction.name, instruction.desc)
+        }
+        _ => "???".to_string(),
+      };
+
+      writeln!(
+        output,
+        "  {:<20} {:>10} {:>6.2}%",
+        name.trim(),
+        count,
+        self.percent(*count)
+      )?;
+    }
+
+    let mut routines: Vec<(u16, Routine)> = self.routines().into_iter().collect();
+
+    routines.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(a.0.cmp(&b.0)));
+
+    writeln!(output)?;
+    writeln!(output, "Subroutines")?;
+    writeln!(
+      output,
+      "  {:<20} {:>8} {:>10} {:>10} {:>10} {:>10}",
+      "ENTRY", "CALLS", "INCLUSIVE", "EXCLUSIVE", "PER CALL", "PER FRAME"
+    )?;
+
+    for (entry, routine) in routines {
+      writeln!(
+        output,
+        "  {:<20} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        symbols.locate(entry),
+        routine.calls,
+        routine.inclusive,
+        routine.exclusive,
+        routine.inclusive / routine.calls.max(1),
+        routine.inclusive / frames,
+      )?;
+    }
+
+    if !self.waits.is_empty() {
+      writeln!(output)?;
+      writeln!(output, "Fx0A waits")?;
+      writeln!(output, "  {:<20} {:>10}", "ADDRESS", "FRAMES")?;
+
+      for (address, count) in self.waits.iter() {
+        writeln!(output, "  {:<20} {:>10}", symbols.locate(*address), count)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn current(&mut self, pc: u16) -> u16 {
+    match self.stack.last() {
+      Some((entry, _)) => *entry,
+      None => *self.root.get_or_insert(pc),
+    }
+  }
+
+  fn percent(&self, count: u64) -> f64 {
+    count as f64 * 100.0 / self.total.max(1) as f64
+  }
+}
+
+impl Default for Profiler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_routines() {
+    let mut chip8: Chip8 = Chip8::new();
+    let mut profiler: Profiler = Profiler::new();
+
+    // 0x200: CALL 0x206 / JP 0x202 / 0x206: LD V0, 01 / RET
+    chip8
+      .load_bytes(
+        &[0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE],
+        false,
+      )
+      .unwrap();
+    chip8.profile(Some(profiler));
+
+    for _ in 0..6 {
+      chip8.step();
+    }
+
+    profiler = chip8.profiler.take().unwrap();
+
+    let routines: BTreeMap<u16, Routine> = profiler.routines();
+
+    assert_eq!(profiler.total(), 6);
+    assert_eq!(profiler.count(0x202), 3);
+    assert_eq!(routines[&0x206].calls, 1);
+    assert_eq!(routines[&0x206].inclusive, 2);
+    assert_eq!(routines[&0x206].exclusive, 2);
+    assert_eq!(routines[&0x200].exclusive, 4);
+    assert_eq!(routines[&0x200].inclusive, 6);
+  }
+}
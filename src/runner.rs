@@ -1,20 +1,62 @@
+use core::ops::Range;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs::create_dir_all;
+use std::fs::read;
+use std::io::stderr;
+use std::path::Path;
+use std::path::PathBuf;
 use std::thread::sleep;
 use std::time::Duration;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
+use crate::afterimage::Afterimage;
+use crate::browser::Browser;
+use crate::browser::Rom;
 use crate::chip8::Chip8;
+use crate::chip8::Draw;
+use crate::chip8::DrawCall;
 use crate::chip8::Mode;
 use crate::chip8::Pitch;
+use crate::config::Config;
+use crate::config::Layout;
+use crate::config::Overrides;
+use crate::coverage::Access;
+use crate::coverage::Coverage;
+use crate::database::Program;
+use crate::gdb::GdbStub;
+use crate::ini::Ini;
 use crate::instruction::Instruction;
+use crate::journal;
+use crate::journal::Journal;
+use crate::journal::Target;
+use crate::keymap::Action;
+use crate::keymap::Input;
+use crate::keymap::Keymap;
+use crate::palette::Color;
+use crate::palette::Palette;
+use crate::profiler::Profiler;
+use crate::scaler::Filter;
 use crate::sdl2::Event;
+use crate::sdl2::GameController;
 use crate::sdl2::SDLK_Keycode;
+use crate::sdl2::SDL_Button;
 use crate::sdl2::SDL_Context;
+use crate::sdl2::SDL_FingerID;
+use crate::sdl2::SDL_GameControllerButton;
+use crate::sdl2::SDL_JoystickID;
 use crate::sdl2::SDL_Rect;
+use crate::sha1::sha1;
+use crate::symbols::Symbols;
+use crate::trace::Tracer;
 
 type FrameSpec = (i32, i32, i32, i32);
 
 const HISTORY: usize = 0x20;
+const FRAME: u64 = 1_000_000_000 / 60; // nanoseconds between renders
+const TITLE: &str = "Chip-8";
+const STACK_WARNING: usize = 0x2; // free stack entries left before warning
 
 const PAD: i32 = 8;
 const PAD2: i32 = PAD * 2;
@@ -28,6 +70,42 @@ const F4: FrameSpec = (PAD2 + 640, PAD2 + 320, 320, 320);
 const CW: i32 = 5; // font char width
 const CH: i32 = 7; // font char height
 
+const KEYPAD: FrameSpec = (F2.0 + 204, F2.1 + 4, 112, 112);
+const KEYS: [u8; 16] = [
+  0x1, 0x2, 0x3, 0xC, // 1 2 3 C
+  0x4, 0x5, 0x6, 0xD, // 4 5 6 D
+  0x7, 0x8, 0x9, 0xE, // 7 8 9 E
+  0xA, 0x0, 0xB, 0xF, // A 0 B F
+];
+const MOUSE: SDL_FingerID = -1; // pointer id of the mouse in `pointers`
+const DEADZONE: i16 = 0x4000; // controller axis travel before it counts as pressed
+
+// Contents of the bottom-right frame, cycled with TAB.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+enum View {
+  Monitors, // symbol file memory monitors
+  Coverage, // memory map colored by access type
+  Sprites,  // memory at I (or a chosen address) decoded as sprites
+}
+
+impl View {
+  fn next(self) -> Self {
+    match self {
+      Self::Monitors => Self::Coverage,
+      Self::Coverage => Self::Sprites,
+      Self::Sprites => Self::Monitors,
+    }
+  }
+}
+
+// Hex value being typed into a register or memory cell.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct Edit {
+  target: Target,
+  text: String,
+}
+
 fn time() -> u64 {
   match SystemTime::now().duration_since(UNIX_EPOCH) {
     Ok(duration) => duration.as_nanos() as u64,
@@ -37,12 +115,43 @@ fn time() -> u64 {
 
 #[repr(C)]
 pub struct ChipRunner {
-  paused: bool,      // flag set if interpreter is halted by user-interaction
-  cycles: u64,       // total cycles executed
-  speed: u64,        // execution speed
-  time: u64,         // execution timestamp
-  hindex: i32,       // opcode history index
-  history: Vec<u16>, // history of opcode execution
+  paused: bool,         // flag set if interpreter is halted by user-interaction
+  resume: bool,         // flag set if the breakpoint at PC is skipped when resuming
+  cycles: u64,          // total cycles executed
+  speed: u64,           // execution speed
+  time: u64,            // execution timestamp
+  hindex: i32,          // opcode history index
+  history: Vec<u16>,    // history of opcode execution
+  gdb: Option<GdbStub>, // remote debugger connection
+  symbols: Symbols,     // labels, breakpoints and monitors of the loaded ROM
+  view: View,           // contents of the bottom-right frame
+  sprite: Option<u16>,  // address shown by the sprite view - follows I if unset
+  edit: Option<Edit>,   // register or memory cell being edited
+  mouse: (i32, i32),    // last known mouse position
+  pointers: BTreeMap<SDL_FingerID, u8>, // keypad keys held by the mouse or fingers
+  keymap: Keymap,       // host key and controller bindings
+  axes: BTreeMap<(SDL_JoystickID, u8), bool>, // controller axes pushed past the dead zone
+  buttons: BTreeSet<(SDL_JoystickID, u8)>, // controller buttons held down
+  palette: Palette,     // colors of the game display
+  afterimage: Afterimage, // flicker reduction applied to the display
+  filter: Filter,       // upscaling applied to the display
+  audio: bool,          // flag set if the sound timer beeps
+  layout: Layout,       // panels shown in the window
+  scale: u32,           // size of a low-res pixel in a play layout window
+  fullscreen: bool,     // flag set if the window covers the screen
+  window: (i32, i32),   // window size
+  relayout: bool,       // flag set if the window must be resized to the layout
+  redraw: bool,         // flag set if the whole window must be repainted
+  frame: u64,           // timestamp of the next render
+  browser: Browser,     // ROM picker shown over the window
+  browsing: bool,       // flag set if the ROM picker is open
+  settings: Ini,        // config file applied to every ROM loaded
+  overrides: Overrides, // command line settings applied over the config file
+  config: Config,       // settings the loaded ROM was configured with
+  ini: Option<PathBuf>, // config file the palette hotkey saves themes to
+  rom: String,          // path of the loaded ROM
+  base: u16,            // address ROMs are loaded at
+  title: String,        // window title naming the loaded ROM
   chip8: Chip8,
 }
 
@@ -50,15 +159,82 @@ impl ChipRunner {
   pub const W: i32 = (Chip8::W as i32 * 5) + 320 + PAD3;
   pub const H: i32 = (Chip8::H as i32 * 5) + 320 + PAD3;
 
+  // Size of the streaming texture the display is rendered into - large enough
+  // for the hi-res display scaled by any filter.
+  pub const TEXTURE: (i32, i32) = (
+    (Chip8::W * Filter::MAX_FACTOR) as i32,
+    (Chip8::H * Filter::MAX_FACTOR) as i32,
+  );
+
   pub fn new() -> Self {
+    let config: Config = Config::new();
+    let chip8: Chip8 = Chip8::new();
+
     Self {
       paused: false,
+      resume: false,
       cycles: 0,
-      speed: 700,
+      speed: config.speed,
       time: 0,
       hindex: 0,
       history: Vec::with_capacity(HISTORY * 8),
-      chip8: Chip8::new(),
+      gdb: None,
+      symbols: Symbols::new(),
+      view: View::Monitors,
+      sprite: None,
+      edit: None,
+      mouse: (0, 0),
+      pointers: BTreeMap::new(),
+      keymap: config.keymap,
+      axes: BTreeMap::new(),
+      buttons: BTreeSet::new(),
+      palette: config.palette,
+      afterimage: Afterimage::new(config.persistence, config.decay),
+      filter: config.filter,
+      audio: config.audio,
+      layout: config.layout,
+      scale: config.scale,
+      fullscreen: config.fullscreen,
+      window: (Self::W, Self::H),
+      relayout: false,
+      redraw: true,
+      frame: 0,
+      browser: Browser::new(&config.roms),
+      browsing: false,
+      settings: Ini::new(),
+      overrides: Overrides::new(),
+      config: Config::new(),
+      ini: None,
+      rom: String::new(),
+      base: Chip8::base(false) as u16,
+      title: format!("{}\0", TITLE),
+      chip8,
+    }
+  }
+
+  // Applies the settings of a config file - call after loading the ROM.
+  pub fn configure(&mut self, config: &Config) {
+    if let Some(mode) = config.mode {
+      self.chip8.mode(mode);
+    }
+
+    self.speed = config.speed;
+    self.palette = config.palette;
+    self.afterimage = Afterimage::new(config.persistence, config.decay);
+    self.filter = config.filter;
+    self.audio = config.audio;
+    self.keymap = config.keymap.clone();
+    self.layout = config.layout;
+    self.scale = config.scale.max(1);
+    self.fullscreen = config.fullscreen;
+    self.relayout = true;
+
+    if let Some(quirks) = config.quirks {
+      self.chip8.quirks(quirks);
+    }
+
+    if config.journal != self.chip8.journal.is_some() {
+      self.chip8.journal(config.journal.then(Journal::new));
     }
   }
 
@@ -73,42 +249,177 @@ impl ChipRunner {
   pub fn slower(&mut self) {
     if self.speed > 100 {
       self.cycles = 0;
-      self.speed -= 200;
+      self.speed = self.speed.saturating_sub(200).max(100);
       self.time = time();
     }
   }
 
+  pub fn keymap(&mut self, keymap: Keymap) {
+    self.keymap = keymap;
+  }
+
+  pub fn attach(&mut self, gdb: GdbStub) {
+    self.gdb = Some(gdb);
+  }
+
+  pub fn symbols(&mut self, symbols: Symbols) {
+    self.symbols = symbols;
+  }
+
+  pub fn trace(&mut self, tracer: Tracer) {
+    self.chip8.trace(Some(tracer));
+  }
+
+  pub fn profile(&mut self, profiler: Profiler) {
+    self.chip8.profile(Some(profiler));
+  }
+
+  pub fn browser(&mut self, browser: Browser) {
+    self.browser = browser;
+  }
+
+  // Keeps a config file to apply to the ROMs loaded later (see `Config::apply`).
+  pub fn settings(&mut self, settings: Ini) {
+    self.settings = settings;
+  }
+
+  pub fn overrides(&mut self, overrides: Overrides) {
+    self.overrides = overrides;
+  }
+
+  // Saves the themes picked with the palette hotkey to a config file as soon
+  // as they are picked.
+  pub fn store(&mut self, path: PathBuf) {
+    self.ini = Some(path);
+  }
+
+  // Returns the settings the loaded ROM was configured with, before any change
+  // made while running.
+  #[inline]
+  pub fn config(&self) -> &Config {
+    &self.config
+  }
+
+  #[inline]
+  pub fn rom(&self) -> &str {
+    &self.rom
+  }
+
+  // Returns the nul-terminated window title of the loaded ROM.
+  #[inline]
+  pub fn title(&self) -> &str {
+    &self.title
+  }
+
+  // Returns the window size that fits the layout.
+  pub fn window_size(&self) -> (i32, i32) {
+    match self.layout {
+      Layout::Debug => (Self::W, Self::H),
+      Layout::Play => (
+        (Chip8::W / 2) as i32 * self.scale as i32,
+        (Chip8::H / 2) as i32 * self.scale as i32,
+      ),
+    }
+  }
+
+  #[inline]
+  pub fn palette(&self) -> Palette {
+    self.palette
+  }
+
+  #[inline]
+  pub fn coverage(&self) -> &Coverage {
+    &self.chip8.coverage
+  }
+
+  pub fn toggle_overlay(&mut self) {
+    let enabled: bool = self.chip8.overlay.is_none();
+
+    self.chip8.overlay(enabled);
+    self.chip8.render = true;
+  }
+
+  // Starts the interpreter halted - execution begins on the first resume or step.
+  pub fn pause(&mut self) {
+    self.paused = true;
+    self.resume = false;
+  }
+
+  pub fn toggle_pause(&mut self) {
+    self.paused = !self.paused;
+    self.resume = !self.paused;
+  }
+
+  // Reverts the most recent instruction while paused.
+  pub fn step_back(&mut self) {
+    if self.paused && self.chip8.step_back() {
+      self.history.pop();
+      self.hindex = self.hindex.min(self.history.len() as i32);
+      self.chip8.render = true;
+    }
+  }
+
+  // Replays a reverted instruction, or executes the next one, while paused.
+  pub fn step_forward(&mut self) {
+    if !self.paused {
+      return;
+    }
+
+    self.clear_overlay();
+
+    if let Some(opcode) = self.chip8.step_forward().or_else(|| self.chip8.step()) {
+      self.push_history(opcode);
+      self.chip8.render = true;
+    }
+  }
+
   // TODO: timing
   // - clock ticks at 1
   // - delay/sound ticks at 1000 / 60
   pub fn run(&mut self, context: &SDL_Context) {
+    let mut controllers: BTreeMap<SDL_JoystickID, GameController> = BTreeMap::new();
+
     'running: loop {
-      if self.poll(context) {
+      if self.poll(context, &mut controllers) || self.poll_gdb() {
         break 'running;
       }
 
-      let count: u64 = (time() - self.time) * self.speed / 1_000_000_000;
+      self.execute();
 
-      if self.paused {
-        self.cycles = count;
-      } else {
-        while self.cycles < count {
-          if let Some(opcode) = self.chip8.step() {
-            self.push_history(opcode);
-          }
+      if time() >= self.frame {
+        self.frame = time() + FRAME;
+        self.render(context);
+        self.chip8.frame();
+      }
 
-          if self.chip8.is_waiting() {
-            self.cycles = count;
-          } else {
-            self.cycles += 1;
-          }
-        }
+      if self.audio && self.chip8.sound > 0 {
+        context.audio.beep();
       }
 
-      self.render(context);
+      sleep(Duration::from_millis(1));
+    }
+
+    self.report();
+  }
+
+  // Runs the interpreter without a window - only useful with a remote debugger.
+  pub fn run_headless(&mut self) {
+    'running: loop {
+      if self.poll_gdb() {
+        break 'running;
+      }
+
+      self.execute();
+
+      if time() >= self.frame {
+        self.frame = time() + FRAME;
+        self.chip8.frame();
+      }
 
       sleep(Duration::from_millis(1));
     }
+
+    self.report();
   }
 
   pub fn mode(&mut self, mode: Mode) {
@@ -117,27 +428,276 @@ impl ChipRunner {
   }
 
   pub fn load(&mut self, path: &str, eti: bool) -> Result<(), &'static str> {
+    self.load_at(path, Chip8::base(eti) as u16)
+  }
+
+  // Loads a ROM and applies its settings from the ROM database, or the mode
+  // detected from its instructions if the ROM is unknown, followed by the
+  // settings of the config file.
+  pub fn load_at(&mut self, path: &str, base: u16) -> Result<(), &'static str> {
+    let buffer: Vec<u8> = read(path).map_err(|_| "Invalid ROM")?;
+    let name: &str = Path::new(path)
+      .file_stem()
+      .and_then(|name| name.to_str())
+      .unwrap_or_default();
+
+    let hash: String = sha1(&buffer);
+    let mut config: Config = Config::for_rom(&buffer, base);
+
+    config.apply(&self.settings, &[name, &hash])?;
+    self.overrides.apply(&mut config, name)?;
+
     self.reset();
-    self.chip8.load(path, eti)
+    self.chip8.load_bytes_at(&buffer, base)?;
+    self.configure(&config);
+    self.config = config;
+    self.rom = path.to_string();
+    self.base = base;
+
+    self.title = match Program::lookup(&hash) {
+      Some(program) if program.authors.is_empty() => format!("{} - {}\0", TITLE, program.title),
+      Some(program) => format!(
+        "{} - {} by {}\0",
+        TITLE,
+        program.title,
+        program.authors.join(", ")
+      ),
+      None => format!("{}\0", TITLE),
+    };
+
+    Ok(())
+  }
+
+  // Switches to a ROM picked in the browser. The window keeps its layout and
+  // size, and the symbols of the previous ROM are dropped.
+  fn open_rom(&mut self, context: &SDL_Context, path: &str) {
+    let (layout, scale, fullscreen): (Layout, u32, bool) =
+      (self.layout, self.scale, self.fullscreen);
+
+    if let Err(error) = self.load_at(path, self.base) {
+      self.browser.fail(error);
+      return;
+    }
+
+    let _ = self.browser.visit(path);
+
+    self.layout = layout;
+    self.scale = scale;
+    self.fullscreen = fullscreen;
+    self.relayout = false;
+    self.redraw = true;
+    self.browsing = false;
+    self.paused = false;
+    self.resume = false;
+    self.symbols = Symbols::new();
+    self.pointers.clear();
+    self.edit = None;
+
+    context.renderer.set_title(&self.title);
+  }
+
+  // Writes the theme of the palette to the global section of the config file.
+  fn save_theme(&self) -> Result<(), &'static str> {
+    let (path, name): (&PathBuf, &str) = match (self.ini.as_ref(), self.palette.name()) {
+      (Some(path), Some(name)) => (path, name),
+      _ => return Ok(()),
+    };
+
+    if let Some(parent) = path.parent() {
+      create_dir_all(parent).map_err(|_| "Invalid Config File")?;
+    }
+
+    Ini::store(&path.to_string_lossy(), "", "theme", name)
+  }
+
+  fn execute(&mut self) {
+    let count: u64 = (time() - self.time) * self.speed / 1_000_000_000;
+
+    if self.paused || self.browsing {
+      self.cycles = count;
+      return;
+    }
+
+    if self.cycles < count {
+      self.clear_overlay();
+    }
+
+    while self.cycles < count {
+      if let Some(gdb) = self.gdb.as_mut() {
+        if !gdb.before_step(&self.chip8) {
+          self.cycles = count;
+          break;
+        }
+      }
+
+      if !self.resume && self.symbols.is_breakpoint(self.chip8.pc) {
+        self.paused = true;
+        self.cycles = count;
+        break;
+      }
+
+      self.resume = false;
+
+      if let Some(opcode) = self.chip8.step() {
+        self.push_history(opcode);
+      }
+
+      if let Some(gdb) = self.gdb.as_mut() {
+        gdb.after_step(&self.chip8);
+      }
+
+      if self.chip8.is_waiting() {
+        self.cycles = count;
+      } else {
+        self.cycles += 1;
+      }
+    }
+  }
+
+  // Writes the profiler report, if profiling is enabled.
+  fn report(&self) {
+    if let Some(profiler) = self.chip8.profiler.as_ref() {
+      let _ = profiler.report(&mut stderr(), &self.symbols);
+    }
+  }
+
+  // Services the remote debugger - returns true if it requested termination.
+  fn poll_gdb(&mut self) -> bool {
+    match self.gdb.as_mut() {
+      Some(gdb) => {
+        gdb.poll(&mut self.chip8);
+        gdb.is_killed()
+      }
+      None => false,
+    }
   }
 
   fn render(&mut self, context: &SDL_Context) {
+    if self.relayout {
+      self.apply_layout(context);
+    }
+
+    if self.redraw {
+      context.renderer.color(0, 0, 0);
+      context.renderer.clear();
+      self.chip8.render = true;
+      self.redraw = false;
+    }
+
+    match self.layout {
+      _ if self.browsing => self.render_browser(context),
+      Layout::Debug => self.render_panels(context),
+      Layout::Play => self.render_play(context),
+    }
+
+    context.renderer.present();
+  }
+
+  // Resizes the window to fit the layout, unless it covers the screen.
+  fn apply_layout(&mut self, context: &SDL_Context) {
+    let (w, h): (i32, i32) = self.window_size();
+
+    context.renderer.set_fullscreen(self.fullscreen);
+    context.renderer.set_minimum_size(Chip8::W as i32 / 2, Chip8::H as i32 / 2);
+
+    if !self.fullscreen {
+      context.renderer.set_size(w, h);
+    }
+
+    self.window = context.renderer.size();
+    self.relayout = false;
+    self.redraw = true;
+  }
+
+  fn render_panels(&mut self, context: &SDL_Context) {
     self.render_frame(context, F2);
     self.render_frame(context, F3);
     self.render_frame(context, F4);
 
     context.renderer.color(255, 255, 255);
 
-    if self.chip8.render {
-      self.render_frame(context, F1);
-      self.render_display(context, F1.0, F1.1);
+    if self.chip8.render || self.chip8.overlay.is_some() || self.afterimage.is_active() {
+      let scale: i32 = if self.chip8.pitch == Pitch::P8 { 10 } else { 5 };
+
+      self.render_screen(context, F1);
+      self.render_display(context, F1.0, F1.1, scale);
+      self.render_overlay(context, F1.0, F1.1);
       self.chip8.render = false;
     }
 
     self.render_debug(context, F2.0 + 4, F2.1 + 4);
+    self.render_keypad(context);
     self.render_history(context, F3.0 + 4, F3.1 + 4);
+    self.render_callstack(context, F3.0 + 4 + F3.2 / 2, F3.1 + 4);
 
-    context.renderer.present();
+    match self.view {
+      View::Monitors => self.render_monitors(context, F4.0 + 4, F4.1 + 4),
+      View::Coverage => self.render_coverage(context, F4.0 + 1, F4.1 + 1),
+      View::Sprites => self.render_sprites(context, F4.0 + 4, F4.1 + 4),
+    }
+  }
+
+  // Fills the window with the display, scaled by the largest integer factor
+  // that fits and centered on the border color.
+  fn render_play(&mut self, context: &SDL_Context) {
+    let (columns, rows): (i32, i32) = match self.chip8.pitch {
+      Pitch::P8 => (Chip8::W as i32 / 2, Chip8::H as i32 / 2),
+      Pitch::P16 => (Chip8::W as i32, Chip8::H as i32),
+    };
+
+    let scale: i32 = (self.window.0 / columns).min(self.window.1 / rows).max(1);
+    let (w, h): (i32, i32) = (columns * scale, rows * scale);
+    let (dx, dy): (i32, i32) = ((self.window.0 - w) / 2, (self.window.1 - h) / 2);
+
+    let (r, g, b): Color = self.palette.border;
+
+    context.renderer.color(r, g, b);
+    context.renderer.fill_rect(0, 0, self.window.0, self.window.1);
+
+    self.render_display(context, dx, dy, scale);
+    self.chip8.render = false;
+  }
+
+  // Lists the ROMs of the browser over the whole window, scrolled to keep the
+  // highlighted one in view.
+  fn render_browser(&self, context: &SDL_Context) {
+    let (w, h): (i32, i32) = self.window;
+    let entries: &[Rom] = self.browser.entries();
+    let rows: usize = ((h - PAD2) / 10 - 3).max(1) as usize;
+    let start: usize = self
+      .browser
+      .index()
+      .saturating_sub(rows / 2)
+      .min(entries.len().saturating_sub(rows));
+
+    context.renderer.color(0, 0, 0);
+    context.renderer.fill_rect(0, 0, w, h);
+
+    for (index, _) in entries.iter().enumerate().skip(start).take(rows) {
+      if index == self.browser.index() {
+        let y: i32 = PAD + (index - start) as i32 * 10 + 30;
+
+        context.renderer.color(48, 64, 160);
+        context.renderer.fill_rect(PAD - 2, y - 2, w - PAD2 + 4, CH + 4);
+      }
+    }
+
+    context.renderer.color(255, 255, 255);
+
+    let mut lines: Lines = Lines::new(context, PAD, PAD);
+
+    lines.write(&format!("ROMs in {}", self.browser.dir()));
+    lines.write("UP/DOWN select - RETURN load - ESCAPE close");
+
+    match self.browser.error() {
+      Some(error) => lines.write(&format!("Error: {}", error)),
+      None if entries.is_empty() => lines.write("No ROMs found"),
+      None => lines.write(""),
+    }
+
+    for rom in entries.iter().skip(start).take(rows) {
+      lines.write(&format!("{:<8} {}", rom.group, rom.title));
+    }
   }
 
   fn clear_frame(&self, context: &SDL_Context, spec: FrameSpec) {
@@ -169,17 +729,114 @@ impl ChipRunner {
       .line(spec.0 + spec.2, spec.1, spec.0 + spec.2, spec.1 + spec.3);
   }
 
-  fn render_display(&self, context: &SDL_Context, dx: i32, dy: i32) {
+  // Clears the game display to the background color and frames it with the border color.
+  fn render_screen(&self, context: &SDL_Context, spec: FrameSpec) {
+    let (r, g, b): Color = self.palette.background;
+
+    context.renderer.color(r, g, b);
+    context.renderer.fill_rect(spec.0, spec.1, spec.2, spec.3);
+
+    let (r, g, b): Color = self.palette.border;
+
+    context.renderer.color(r, g, b);
+    context.renderer.line(spec.0, spec.1, spec.0 + spec.2, spec.1);
+    context.renderer.line(spec.0, spec.1, spec.0, spec.1 + spec.3);
+    context.renderer.line(spec.0, spec.1 + spec.3, spec.0 + spec.2, spec.1 + spec.3);
+    context.renderer.line(spec.0 + spec.2, spec.1, spec.0 + spec.2, spec.1 + spec.3);
+  }
+
+  // Writes the display into the streaming texture and copies it to the window,
+  // each display pixel `scale` screen pixels wide.
+  fn render_display(&mut self, context: &SDL_Context, dx: i32, dy: i32, scale: i32) {
+    let (columns, rows): (usize, usize) = match self.chip8.pitch {
+      Pitch::P8 => (Chip8::W / 2, Chip8::H / 2),
+      Pitch::P16 => (Chip8::W, Chip8::H),
+    };
+
+    let colors: &[Color] = self.afterimage.frame(&self.chip8.display, &self.palette);
+    let visible: Vec<Color> = colors
+      .chunks_exact(Chip8::W)
+      .take(rows)
+      .flat_map(|line| line[..columns].iter().copied())
+      .collect();
+
+    let factor: usize = self.filter.factor();
+    let image: Vec<Color> = self.filter.apply(&visible, columns, rows);
+
+    let result: Result<(), &'static str> = context.display.with_lock(|pixels, pitch| {
+      let lines = pixels.chunks_exact_mut(pitch).zip(image.chunks_exact(columns * factor));
+
+      for (row, line) in lines {
+        for (pixel, (r, g, b)) in row.chunks_exact_mut(4).zip(line.iter()) {
+          pixel.copy_from_slice(&u32::from_be_bytes([0xFF, *r, *g, *b]).to_ne_bytes());
+        }
+      }
+    });
+
+    if result.is_ok() {
+      context.renderer.copy(
+        context.display,
+        &SDL_Rect {
+          x: 0,
+          y: 0,
+          w: (columns * factor) as i32,
+          h: (rows * factor) as i32,
+        },
+        &SDL_Rect {
+          x: dx,
+          y: dy,
+          w: columns as i32 * scale,
+          h: rows as i32 * scale,
+        },
+      );
+    }
+  }
+
+  // Outlines the sprites drawn by the last batch of instructions - pixels erased by a
+  // partially overlapping sprite (a collision) are red, pixels erased by a
+  // sprite drawn over itself (the usual erase before moving) are faded blue.
+  fn render_overlay(&self, context: &SDL_Context, dx: i32, dy: i32) {
+    let calls: &[DrawCall] = match self.chip8.overlay.as_ref() {
+      Some(overlay) => overlay,
+      None => return,
+    };
+
     let scale: i32 = if self.chip8.pitch == Pitch::P8 { 10 } else { 5 };
+    let (right, bottom): (i32, i32) = (dx + F1.2, dy + F1.3);
+
+    for call in calls.iter() {
+      for &index in call.hits.iter() {
+        if call.erase && self.chip8.display[index as usize] != 0 {
+          continue;
+        }
 
-    for (index, &pixel) in self.chip8.display.iter().enumerate() {
-      if pixel == 1 {
-        let x: i32 = dx + ((index % Chip8::W) as i32 * scale);
-        let y: i32 = dy + ((index / Chip8::W) as i32 * scale);
+        if call.erase {
+          context.renderer.color(48, 64, 160);
+        } else {
+          context.renderer.color(255, 48, 48);
+        }
+
+        let x: i32 = dx + (index as usize % Chip8::W) as i32 * scale;
+        let y: i32 = dy + (index as usize / Chip8::W) as i32 * scale;
 
         context.renderer.fill_rect(x, y, scale, scale);
       }
+
+      let x1: i32 = dx + call.x as i32 * scale;
+      let y1: i32 = dy + call.y as i32 * scale;
+      let x2: i32 = (x1 + 8 * scale).min(right);
+      let y2: i32 = (y1 + call.rows as i32 * scale).min(bottom);
+
+      if x1 < right && y1 < bottom {
+        context.renderer.color(255, 200, 0);
+        context.renderer.line(x1, y1, x2, y1);
+        context.renderer.line(x1, y2, x2, y2);
+        context.renderer.line(x1, y1, x1, y2);
+        context.renderer.line(x2, y1, x2, y2);
+      }
     }
+
+    context.renderer.color(255, 255, 255);
   }
 
   fn render_debug(&self, context: &SDL_Context, dx: i32, dy: i32) {
@@ -189,19 +846,16 @@ impl ChipRunner {
       lines.write(&format!("V{:x} = {:#04X}", index, value));
     }
 
-    lines.write("");
-    lines.write(&format!("Keys  = {:#018b}", self.chip8.keys));
     lines.write("");
     lines.write(&format!("Speed = {}", self.speed));
     lines.write(&format!("Cycle = {}", self.cycles));
 
-    lines.set(dx + 98, dy);
-
-    for (index, value) in self.chip8.stack.iter().enumerate() {
-      lines.write(&format!("S{:x} = {:#04X}", index, value));
+    if let Some(edit) = self.edit.as_ref() {
+      lines.write("");
+      lines.write(&format!("Edit {} = {}_", edit.target, edit.text));
     }
 
-    lines.set(dx + 98 + 98, dy);
+    lines.set(dx + 98, dy);
 
     lines.write(&format!("I  = {:#04X}", self.chip8.reg_i));
     lines.write("");
@@ -212,21 +866,424 @@ impl ChipRunner {
     lines.write(&format!("ST = {:#04X}", self.chip8.sound));
   }
 
+  // Draws the hex keypad in its original layout with held keys lit.
+  fn render_keypad(&self, context: &SDL_Context) {
+    for (index, key) in KEYS.iter().enumerate() {
+      let x: i32 = KEYPAD.0 + (index as i32 % 4) * KEYPAD.2 / 4;
+      let y: i32 = KEYPAD.1 + (index as i32 / 4) * KEYPAD.3 / 4;
+
+      if self.chip8.key_pressed(*key) {
+        context.renderer.color(96, 160, 96);
+      } else {
+        context.renderer.color(48, 48, 48);
+      }
+
+      context.renderer.fill_rect(x, y, KEYPAD.2 / 4 - 2, KEYPAD.3 / 4 - 2);
+
+      render_text(
+        context,
+        &format!("{:X}", key),
+        x + (KEYPAD.2 / 4 - 2 - CW) / 2,
+        y + (KEYPAD.3 / 4 - 2 - CH) / 2,
+      );
+    }
+
+    context.renderer.color(255, 255, 255);
+  }
+
   fn render_history(&self, context: &SDL_Context, dx: i32, dy: i32) {
     let mut lines: Lines = Lines::new(context, dx, dy);
 
     for opcode in self.history_window(HISTORY as i32 - 1) {
       if let Some(instruction) = Instruction::find(*opcode) {
         lines.write(&format!(
-          "[{opcode:#06X}] {name: <8} - {desc}",
+          "[{opcode:#06X}] {text}",
           opcode = opcode,
-          name = instruction.name,
-          desc = instruction.desc,
+          text = self.symbols.disassemble(instruction, *opcode),
         ));
       }
     }
   }
 
+  fn render_callstack(&self, context: &SDL_Context, dx: i32, dy: i32) {
+    let mut lines: Lines = Lines::new(context, dx, dy);
+    let depth: usize = self.chip8.sp as usize;
+    let limit: usize = self.chip8.stack.len();
+
+    lines.write(&format!("Call Stack ({}/{})", depth, limit));
+    lines.write("");
+
+    // Innermost frame first - the return address points just past the CALL
+    for index in (0..depth).rev() {
+      lines.write(&format!(
+        "{depth:>2} {callee:<16} <- {caller}",
+        depth = index + 1,
+        callee = self.symbols.locate(self.chip8.calls[index]),
+        caller = self.symbols.locate(self.chip8.stack[index].wrapping_sub(2)),
+      ));
+    }
+
+    context.texture.color(255, 96, 96);
+
+    if depth + STACK_WARNING >= limit {
+      lines.write("");
+      lines.write(&format!("! Stack near limit ({}/{})", depth, limit));
+    }
+
+    if self.chip8.underflow {
+      lines.write("");
+      lines.write("! RET with empty stack");
+    }
+
+    if self.chip8.overflow {
+      lines.write("");
+      lines.write("! CALL with full stack");
+    }
+
+    context.texture.color(255, 255, 255);
+  }
+
+  fn render_monitors(&self, context: &SDL_Context, dx: i32, dy: i32) {
+    let mut lines: Lines = Lines::new(context, dx, dy);
+
+    for monitor in self.symbols.monitors() {
+      lines.write(&format!("{} ({:#06X})", monitor.name, monitor.address));
+
+      for row in (0..monitor.length as usize).step_by(8) {
+        let start: usize = monitor.address as usize + row;
+        let end: usize =
+          (start + (monitor.length as usize - row).min(8)).min(self.chip8.memory.len());
+
+        let bytes: Vec<String> = self.chip8.memory[start.min(end)..end]
+          .iter()
+          .map(|byte| format!("{:02X}", byte))
+          .collect();
+
+        lines.write(&format!("  {:04X}: {}", start, bytes.join(" ")));
+      }
+    }
+  }
+
+  // Draws one 5x5 cell per byte - green for executed, blue for read and red
+  // for written bytes, mixed when a byte was accessed in several ways.
+  fn render_coverage(&self, context: &SDL_Context, dx: i32, dy: i32) {
+    let coverage: &Coverage = &self.chip8.coverage;
+    let rom: Range<u16> = coverage.rom();
+
+    for address in 0..self.chip8.memory.len() as u16 {
+      let access: Access = coverage.get(address);
+
+      let (r, g, b): (u8, u8, u8) = if address == self.chip8.pc || address == self.chip8.pc + 1 {
+        (255, 255, 255)
+      } else if !access.is_empty() {
+        (
+          if access.contains(Access::WRITE) { 224 } else { 32 },
+          if access.contains(Access::EXEC) { 192 } else { 32 },
+          if access.contains(Access::READ) { 224 } else { 32 },
+        )
+      } else if rom.contains(&address) {
+        (64, 64, 64)
+      } else {
+        continue;
+      };
+
+      let x: i32 = dx + (address as i32 % 64) * 5;
+      let y: i32 = dy + (address as i32 / 64) * 5;
+
+      context.renderer.color(r, g, b);
+      context.renderer.fill_rect(x, y, 4, 4);
+    }
+
+    context.renderer.color(255, 255, 255);
+  }
+
+  fn render_sprites(&self, context: &SDL_Context, dx: i32, dy: i32) {
+    const SCALE: i32 = 4;
+
+    let memory: &[u8] = &self.chip8.memory;
+    let mut lines: Lines = Lines::new(context, dx, dy);
+    let mut address: u16 = self.sprite.unwrap_or(self.chip8.reg_i);
+    let mut y: i32 = dy + 14;
+
+    lines.write(&match self.sprite {
+      Some(_) => format!("Sprites @ {:#06X} (HOME follows I)", address),
+      None => format!("Sprites @ I = {:#06X}", address),
+    });
+
+    loop {
+      let (width, rows, draw): (u8, u8, Option<Draw>) = self.chip8.sprite(address);
+      let pitch: u16 = width as u16 / 8;
+      let size: u16 = rows as u16 * pitch;
+
+      if address as usize + size as usize > memory.len() || y + rows as i32 * SCALE > dy + F4.3 - 8 {
+        break;
+      }
+
+      for row in 0..rows as u16 {
+        for column in 0..width as u16 {
+          let byte: u8 = memory[(address + row * pitch + column / 8) as usize];
+
+          if byte & (0x80 >> (column % 8)) != 0 {
+            context.renderer.fill_rect(
+              dx + column as i32 * SCALE,
+              y + row as i32 * SCALE,
+              SCALE,
+              SCALE,
+            );
+          }
+        }
+      }
+
+      lines.set(dx + 16 * SCALE + 8, y);
+      lines.write(&format!("{:04X}  {}x{}", address, width, rows));
+      lines.write(&match draw {
+        Some(draw) => format!("DRW @ {}", self.symbols.locate(draw.pc)),
+        None => "Not drawn".to_string(),
+      });
+
+      y += (rows as i32 * SCALE).max(20) + 6;
+      address += size;
+    }
+  }
+
+  // Moves the sprite view by one sprite, detaching it from I.
+  fn scroll_sprites(&mut self, forward: bool) {
+    let address: u16 = self.sprite.unwrap_or(self.chip8.reg_i);
+    let (width, rows, _): (u8, u8, Option<Draw>) = self.chip8.sprite(address);
+    let size: u16 = rows as u16 * width as u16 / 8;
+
+    self.sprite = Some(if forward {
+      address.saturating_add(size).min(self.chip8.memory.len() as u16 - 1)
+    } else {
+      address.saturating_sub(size)
+    });
+  }
+
+  // Moves a mouse button or finger onto `key`, releasing the key it held before.
+  fn point(&mut self, pointer: SDL_FingerID, key: Option<u8>) {
+    let held: Option<u8> = self.pointers.get(&pointer).copied();
+
+    if held == key {
+      return;
+    }
+
+    if let Some(held) = held {
+      self.pointers.remove(&pointer);
+      self.chip8.keyrelease(held);
+    }
+
+    if let Some(key) = key {
+      self.pointers.insert(pointer, key);
+      self.chip8.keypress(key);
+    }
+  }
+
+  fn touch(&mut self, finger: SDL_FingerID, x: f32, y: f32, down: bool) {
+    let key: Option<u8> = self.keypad_at(
+      (x * self.window.0 as f32) as i32,
+      (y * self.window.1 as f32) as i32,
+    );
+
+    if down || self.pointers.contains_key(&finger) {
+      self.point(finger, key);
+    }
+  }
+
+  // Starts editing the register or memory cell under the cursor.
+  fn click(&mut self, x: i32, y: i32) {
+    self.edit = self.hit_test(x, y).map(|target| Edit {
+      target,
+      text: String::new(),
+    });
+  }
+
+  // Returns the keypad key under a window position - the play layout has no keypad.
+  fn keypad_at(&self, x: i32, y: i32) -> Option<u8> {
+    match self.layout {
+      _ if self.browsing => None,
+      Layout::Debug => keypad_key(x, y),
+      Layout::Play => None,
+    }
+  }
+
+  fn hit_test(&self, x: i32, y: i32) -> Option<Target> {
+    if self.layout != Layout::Debug || self.browsing {
+      return None;
+    }
+
+    if inside(F2, x, y) {
+      let (column, row): (i32, i32) = ((x - F2.0 - 4) / 98, (y - F2.1 - 4) / 10);
+
+      return match (column, row) {
+        (0, 0..=15) => Some(Target::V(row as u8)),
+        (1, 0) => Some(Target::I),
+        (1, 2) => Some(Target::Pc),
+        (1, 3) => Some(Target::Sp),
+        (1, 5) => Some(Target::Dt),
+        (1, 6) => Some(Target::St),
+        _ => None,
+      };
+    }
+
+    if !inside(F4, x, y) {
+      return None;
+    }
+
+    match self.view {
+      View::Monitors => self.monitor_cell(x - F4.0 - 4, y - F4.1 - 4),
+      View::Coverage => {
+        let (column, row): (i32, i32) = ((x - F4.0 - 1) / 5, (y - F4.1 - 1) / 5);
+        let address: i32 = row * 64 + column;
+
+        if column < 64 && address < self.chip8.memory.len() as i32 {
+          Some(Target::Memory(address as u16))
+        } else {
+          None
+        }
+      }
+      View::Sprites => None,
+    }
+  }
+
+  // Maps a position within the monitor view to the byte drawn there.
+  fn monitor_cell(&self, x: i32, y: i32) -> Option<Target> {
+    let (column, row): (i32, i32) = (x / (CW + 2), y / 10);
+    let mut line: i32 = 0;
+
+    for monitor in self.symbols.monitors() {
+      line += 1;
+
+      for offset in (0..monitor.length).step_by(8) {
+        let cell: i32 = column - 8;
+        let count: i32 = (monitor.length - offset).min(8) as i32;
+
+        if line == row && cell >= 0 && cell % 3 < 2 && cell / 3 < count {
+          return Some(Target::Memory(monitor.address + offset + (cell / 3) as u16));
+        }
+
+        line += 1;
+      }
+    }
+
+    None
+  }
+
+  fn edit_key(&mut self, keycode: SDLK_Keycode) {
+    let edit: &mut Edit = match self.edit.as_mut() {
+      Some(edit) => edit,
+      None => return,
+    };
+
+    match keycode {
+      SDLK_Keycode::SDLK_RETURN | SDLK_Keycode::SDLK_KP_ENTER => self.commit_edit(),
+      SDLK_Keycode::SDLK_ESCAPE => self.edit = None,
+      SDLK_Keycode::SDLK_BACKSPACE => {
+        edit.text.pop();
+      }
+      _ => {
+        let digits: usize = format!("{:X}", journal::limit(edit.target)).len();
+
+        if let Some(digit) = hex_digit(keycode) {
+          if edit.text.len() < digits {
+            edit.text.push(digit);
+          }
+        }
+      }
+    }
+  }
+
+  // Moves through the ROM browser, or loads or closes it.
+  fn browse_key(&mut self, context: &SDL_Context, keycode: SDLK_Keycode, scancode: u32) {
+    let page: i32 = (self.window.1 - PAD2) / 10 - 3;
+
+    match keycode {
+      SDLK_Keycode::SDLK_UP => self.browser.select(-1),
+      SDLK_Keycode::SDLK_DOWN => self.browser.select(1),
+      SDLK_Keycode::SDLK_PAGEUP => self.browser.select(-page),
+      SDLK_Keycode::SDLK_PAGEDOWN => self.browser.select(page),
+      SDLK_Keycode::SDLK_HOME => self.browser.select(i32::MIN / 2),
+      SDLK_Keycode::SDLK_END => self.browser.select(i32::MAX / 2),
+      SDLK_Keycode::SDLK_RETURN | SDLK_Keycode::SDLK_KP_ENTER => self.open_selected(context),
+      SDLK_Keycode::SDLK_ESCAPE => self.toggle_browser(),
+      _ if self.keymap.get(Input::Key(scancode)) == Some(Action::Browse) => self.toggle_browser(),
+      _ => {}
+    }
+  }
+
+  fn browse_button(&mut self, context: &SDL_Context, button: SDL_GameControllerButton) {
+    match button {
+      SDL_GameControllerButton::DPAD_UP => self.browser.select(-1),
+      SDL_GameControllerButton::DPAD_DOWN => self.browser.select(1),
+      SDL_GameControllerButton::A => self.open_selected(context),
+      SDL_GameControllerButton::B => self.toggle_browser(),
+      _ if self.keymap.get(Input::Button(button as u8)) == Some(Action::Browse) => {
+        self.toggle_browser()
+      }
+      _ => {}
+    }
+  }
+
+  fn open_selected(&mut self, context: &SDL_Context) {
+    if let Some(path) = self.browser.selected().map(|rom| rom.path.clone()) {
+      self.open_rom(context, &path);
+    }
+  }
+
+  fn toggle_browser(&mut self) {
+    self.browsing = !self.browsing;
+    self.redraw = true;
+
+    if self.browsing {
+      self.browser.scan();
+    }
+  }
+
+  // Stores the value being edited - values out of range keep the edit open.
+  fn commit_edit(&mut self) {
+    if let Some(edit) = self.edit.take().filter(|edit| !edit.text.is_empty()) {
+      let stored: bool = u16::from_str_radix(&edit.text, 16)
+        .map_err(|_| "Invalid Value")
+        .and_then(|value| journal::edit(&mut self.chip8, edit.target, value))
+        .is_ok();
+
+      if stored {
+        self.chip8.render = true;
+      } else {
+        self.edit = Some(edit);
+      }
+    }
+  }
+
+  // Scrolls the history or sprite view under the cursor.
+  fn wheel(&mut self, amount: i32) {
+    let (x, y): (i32, i32) = self.mouse;
+
+    if self.browsing {
+      self.browser.select(-amount);
+      return;
+    }
+
+    if self.layout != Layout::Debug {
+      return;
+    }
+
+    if inside(F3, x, y) {
+      let end: i32 = self.history.len() as i32;
+      let start: i32 = (HISTORY as i32 - 1).min(end);
+
+      self.hindex = (self.hindex - amount).clamp(start, end);
+    } else if inside(F4, x, y) && self.view == View::Sprites {
+      self.scroll_sprites(amount < 0);
+    }
+  }
+
+  #[inline]
+  fn clear_overlay(&mut self) {
+    if let Some(overlay) = self.chip8.overlay.as_mut() {
+      overlay.clear();
+    }
+  }
+
   #[inline]
   fn reset(&mut self) {
     self.cycles = 0;
@@ -235,46 +1292,181 @@ impl ChipRunner {
     self.history.clear();
   }
 
-  fn poll(&mut self, context: &SDL_Context) -> bool {
+  // Runs the action bound to a host key - returns true if the user quit.
+  fn action(&mut self, action: Action) -> bool {
+    match action {
+      Action::Key(key) => self.chip8.keypress(key),
+      Action::Quit => return true,
+      Action::Pause => self.toggle_pause(),
+      Action::Faster => self.faster(),
+      Action::Slower => self.slower(),
+      Action::View => self.view = self.view.next(),
+      Action::Overlay => self.toggle_overlay(),
+      Action::StepBack => self.step_back(),
+      Action::StepForward => self.step_forward(),
+      Action::SpritesUp => self.scroll_sprites(false),
+      Action::SpritesDown => self.scroll_sprites(true),
+      Action::SpritesFollow => self.sprite = None,
+      Action::SpritesStart => self.sprite = Some(0),
+      Action::Layout => {
+        self.layout = self.layout.next();
+        self.relayout = true;
+      }
+      Action::Fullscreen => {
+        self.fullscreen = !self.fullscreen;
+        self.relayout = true;
+      }
+      Action::Palette => {
+        self.palette = self.palette.next();
+        self.overrides.palette = Some(self.palette);
+        self.chip8.render = true;
+
+        let _ = self.save_theme();
+      }
+      Action::Browse => self.toggle_browser(),
+    }
+
+    false
+  }
+
+  // Presses or releases the action bound to a host input - returns true if the user quit.
+  fn input(&mut self, input: Input, down: bool) -> bool {
+    match self.keymap.get(input) {
+      Some(Action::Key(key)) if !down => self.chip8.keyrelease(key),
+      Some(action) if down => return self.action(action),
+      _ => {}
+    }
+
+    false
+  }
+
+  // Presses or releases a controller button, remembering which are held so
+  // they can be released when the controller is unplugged.
+  fn button(&mut self, id: SDL_JoystickID, button: u8, down: bool) -> bool {
+    if down {
+      self.buttons.insert((id, button));
+    } else {
+      self.buttons.remove(&(id, button));
+    }
+
+    self.input(Input::Button(button), down)
+  }
+
+  // Turns controller axis motion into presses of the direction it was pushed to.
+  fn axis(&mut self, id: SDL_JoystickID, axis: u8, value: i16) -> bool {
+    let direction: Option<bool> = match value {
+      _ if value >= DEADZONE => Some(true),
+      _ if value <= -DEADZONE => Some(false),
+      _ => None,
+    };
+
+    let previous: Option<bool> = match direction {
+      Some(positive) => self.axes.insert((id, axis), positive),
+      None => self.axes.remove(&(id, axis)),
+    };
+
+    if previous == direction {
+      return false;
+    }
+
+    if let Some(positive) = previous {
+      self.input(Input::Axis(axis, positive), false);
+    }
+
+    match direction {
+      Some(positive) => self.input(Input::Axis(axis, positive), true),
+      None => false,
+    }
+  }
+
+  fn poll<'a>(
+    &mut self,
+    context: &SDL_Context<'_, 'a>,
+    controllers: &mut BTreeMap<SDL_JoystickID, GameController<'a>>,
+  ) -> bool {
     while let Some(event) = context.token.poll() {
+      if let (Some(_), Event::KeyDown(keycode, _)) = (self.edit.as_ref(), event) {
+        self.edit_key(keycode);
+        continue;
+      }
+
+      match event {
+        Event::KeyDown(keycode, scancode) if self.browsing => {
+          self.browse_key(context, keycode, scancode as u32);
+          continue;
+        }
+        Event::ControllerDown(_, button) if self.browsing => {
+          self.browse_button(context, button);
+          continue;
+        }
+        _ => {}
+      }
+
       match event {
         Event::Quit => return true,
-        Event::KeyDown(SDLK_Keycode::SDLK_ESCAPE) => return true,
-        Event::KeyDown(SDLK_Keycode::SDLK_UP) => self.faster(),
-        Event::KeyDown(SDLK_Keycode::SDLK_DOWN) => self.slower(),
-        Event::KeyDown(SDLK_Keycode::SDLK_SPACE) => self.paused = !self.paused,
-        Event::KeyDown(SDLK_Keycode::SDLK_1) => self.chip8.keypress(0x1),
-        Event::KeyDown(SDLK_Keycode::SDLK_2) => self.chip8.keypress(0x2),
-        Event::KeyDown(SDLK_Keycode::SDLK_3) => self.chip8.keypress(0x3),
-        Event::KeyDown(SDLK_Keycode::SDLK_4) => self.chip8.keypress(0xC),
-        Event::KeyDown(SDLK_Keycode::SDLK_q) => self.chip8.keypress(0x4),
-        Event::KeyDown(SDLK_Keycode::SDLK_w) => self.chip8.keypress(0x5),
-        Event::KeyDown(SDLK_Keycode::SDLK_e) => self.chip8.keypress(0x6),
-        Event::KeyDown(SDLK_Keycode::SDLK_r) => self.chip8.keypress(0xD),
-        Event::KeyDown(SDLK_Keycode::SDLK_a) => self.chip8.keypress(0x7),
-        Event::KeyDown(SDLK_Keycode::SDLK_s) => self.chip8.keypress(0x8),
-        Event::KeyDown(SDLK_Keycode::SDLK_d) => self.chip8.keypress(0x9),
-        Event::KeyDown(SDLK_Keycode::SDLK_f) => self.chip8.keypress(0xE),
-        Event::KeyDown(SDLK_Keycode::SDLK_z) => self.chip8.keypress(0xA),
-        Event::KeyDown(SDLK_Keycode::SDLK_x) => self.chip8.keypress(0x0),
-        Event::KeyDown(SDLK_Keycode::SDLK_c) => self.chip8.keypress(0xB),
-        Event::KeyDown(SDLK_Keycode::SDLK_v) => self.chip8.keypress(0xF),
-        Event::KeyUp(SDLK_Keycode::SDLK_1) => self.chip8.keyrelease(0x1),
-        Event::KeyUp(SDLK_Keycode::SDLK_2) => self.chip8.keyrelease(0x2),
-        Event::KeyUp(SDLK_Keycode::SDLK_3) => self.chip8.keyrelease(0x3),
-        Event::KeyUp(SDLK_Keycode::SDLK_4) => self.chip8.keyrelease(0xC),
-        Event::KeyUp(SDLK_Keycode::SDLK_q) => self.chip8.keyrelease(0x4),
-        Event::KeyUp(SDLK_Keycode::SDLK_w) => self.chip8.keyrelease(0x5),
-        Event::KeyUp(SDLK_Keycode::SDLK_e) => self.chip8.keyrelease(0x6),
-        Event::KeyUp(SDLK_Keycode::SDLK_r) => self.chip8.keyrelease(0xD),
-        Event::KeyUp(SDLK_Keycode::SDLK_a) => self.chip8.keyrelease(0x7),
-        Event::KeyUp(SDLK_Keycode::SDLK_s) => self.chip8.keyrelease(0x8),
-        Event::KeyUp(SDLK_Keycode::SDLK_d) => self.chip8.keyrelease(0x9),
-        Event::KeyUp(SDLK_Keycode::SDLK_f) => self.chip8.keyrelease(0xE),
-        Event::KeyUp(SDLK_Keycode::SDLK_z) => self.chip8.keyrelease(0xA),
-        Event::KeyUp(SDLK_Keycode::SDLK_x) => self.chip8.keyrelease(0x0),
-        Event::KeyUp(SDLK_Keycode::SDLK_c) => self.chip8.keyrelease(0xB),
-        Event::KeyUp(SDLK_Keycode::SDLK_v) => self.chip8.keyrelease(0xF),
+        Event::KeyDown(_, scancode) => {
+          if self.input(Input::Key(scancode as u32), true) {
+            return true;
+          }
+        }
+        Event::KeyUp(_, scancode) => {
+          self.input(Input::Key(scancode as u32), false);
+        }
+        Event::ControllerDown(id, button) => {
+          if self.button(id, button as u8, true) {
+            return true;
+          }
+        }
+        Event::ControllerUp(id, button) => {
+          self.button(id, button as u8, false);
+        }
+        Event::ControllerAxis(id, axis, value) => {
+          if self.axis(id, axis as u8, value) {
+            return true;
+          }
+        }
+        Event::ControllerAdded(index) => {
+          if let Ok(controller) = context.token.open_controller(index) {
+            controllers.insert(controller.id(), controller);
+          }
+        }
+        Event::ControllerRemoved(id) => {
+          controllers.remove(&id);
+
+          for axis in 0..6 {
+            self.axis(id, axis, 0);
+          }
+
+          let held: Vec<(SDL_JoystickID, u8)> = self
+            .buttons
+            .range((id, 0)..=(id, u8::MAX))
+            .copied()
+            .collect();
+
+          for (id, button) in held {
+            self.button(id, button, false);
+          }
+        }
+        Event::MouseDown(SDL_Button::LEFT, x, y) => match self.keypad_at(x, y) {
+          Some(key) => self.point(MOUSE, Some(key)),
+          None => self.click(x, y),
+        },
+        Event::MouseUp(SDL_Button::LEFT, _, _) => self.point(MOUSE, None),
+        Event::MouseMove(x, y) => {
+          self.mouse = (x, y);
+
+          if self.pointers.contains_key(&MOUSE) {
+            self.point(MOUSE, self.keypad_at(x, y));
+          }
+        }
+        Event::TouchDown(finger, x, y) => self.touch(finger, x, y, true),
+        Event::TouchMove(finger, x, y) => self.touch(finger, x, y, false),
+        Event::TouchUp(finger, _, _) => self.point(finger, None),
+        Event::MouseWheel(_, y) => self.wheel(y),
+        Event::WindowResized(w, h) => {
+          self.window = (w, h);
+          self.redraw = true;
+        }
         _ => {}
       }
     }
@@ -313,6 +1505,30 @@ impl Default for ChipRunner {
   }
 }
 
+fn inside(spec: FrameSpec, x: i32, y: i32) -> bool {
+  x >= spec.0 && y >= spec.1 && x < spec.0 + spec.2 && y < spec.1 + spec.3
+}
+
+fn keypad_key(x: i32, y: i32) -> Option<u8> {
+  if !inside(KEYPAD, x, y) {
+    return None;
+  }
+
+  let column: i32 = (x - KEYPAD.0) * 4 / KEYPAD.2;
+  let row: i32 = (y - KEYPAD.1) * 4 / KEYPAD.3;
+
+  Some(KEYS[(row * 4 + column) as usize])
+}
+
+fn hex_digit(keycode: SDLK_Keycode) -> Option<char> {
+  match keycode as u32 {
+    code @ 0x30..=0x39 | code @ 0x61..=0x66 => {
+      char::from_u32(code).map(|digit| digit.to_ascii_uppercase())
+    }
+    _ => None,
+  }
+}
+
 fn render_text(context: &SDL_Context, text: &str, x: i32, y: i32) {
   let mut source: SDL_Rect = SDL_Rect {
     x: 0,
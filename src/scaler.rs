@@ -0,0 +1,192 @@
+use crate::palette::mix;
+use crate::palette::Color;
+
+// Pixel-art upscaling applied to the display before it is uploaded to SDL.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Filter {
+  Nearest,   // square pixels
+  Scale2x,   // EPX - rounds off the corners of diagonal edges
+  Scale3x,   // AdvMAME3x - Scale2x with a 3x3 output per pixel
+  Hqx,       // Scale2x with the new corners blended into the pixel
+  Scanlines, // every second line at half brightness
+}
+
+impl Filter {
+  pub const MAX_FACTOR: usize = 3;
+
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    match input.trim() {
+      "nearest" => Ok(Self::Nearest),
+      "scale2x" | "epx" => Ok(Self::Scale2x),
+      "scale3x" => Ok(Self::Scale3x),
+      "hqx" => Ok(Self::Hqx),
+      "scanlines" => Ok(Self::Scanlines),
+      _ => Err("Invalid Filter"),
+    }
+  }
+
+  // Output pixels per input pixel in each direction.
+  pub fn factor(self) -> usize {
+    match self {
+      Self::Nearest => 1,
+      Self::Scale2x | Self::Hqx | Self::Scanlines => 2,
+      Self::Scale3x => 3,
+    }
+  }
+
+  // Scales a `w` by `h` image by `factor()` in each direction.
+  pub fn apply(self, input: &[Color], w: usize, h: usize) -> Vec<Color> {
+    let factor: usize = self.factor();
+    let mut output: Vec<Color> = vec![(0, 0, 0); w * h * factor * factor];
+
+    for y in 0..h {
+      for x in 0..w {
+        let block: [Color; 9] = match self {
+          Self::Nearest => [input[y * w + x]; 9],
+          Self::Scale2x => scale2x(&neighbors(input, w, h, x, y), false),
+          Self::Hqx => scale2x(&neighbors(input, w, h, x, y), true),
+          Self::Scale3x => scale3x(&neighbors(input, w, h, x, y)),
+          Self::Scanlines => {
+            let pixel: Color = input[y * w + x];
+            let dark: Color = mix(pixel, (0, 0, 0), 128);
+
+            [pixel, pixel, dark, dark, pixel, pixel, pixel, pixel, pixel]
+          }
+        };
+
+        for (index, color) in block.iter().take(factor * factor).enumerate() {
+          let (dx, dy): (usize, usize) = (index % factor, index / factor);
+
+          output[(y * factor + dy) * w * factor + x * factor + dx] = *color;
+        }
+      }
+    }
+
+    output
+  }
+}
+
+// Returns the 3x3 block around a pixel, repeating the pixel past the edges.
+fn neighbors(input: &[Color], w: usize, h: usize, x: usize, y: usize) -> [Color; 9] {
+  let mut block: [Color; 9] = [input[y * w + x]; 9];
+
+  for (index, color) in block.iter_mut().enumerate() {
+    let (nx, ny): (isize, isize) = (
+      x as isize + (index % 3) as isize - 1,
+      y as isize + (index / 3) as isize - 1,
+    );
+
+    if nx >= 0 && ny >= 0 && (nx as usize) < w && (ny as usize) < h {
+      *color = input[ny as usize * w + nx as usize];
+    }
+  }
+
+  block
+}
+
+// Scale2x on the 3x3 block `[a, b, c, d, e, f, g, h, i]` around `e` - returns
+// the 2x2 output in the first four entries.
+fn scale2x(block: &[Color; 9], blend: bool) -> [Color; 9] {
+  let [_, b, _, d, e, f, _, h, _]: [Color; 9] = *block;
+  let corner = |edge: bool, color: Color| -> Color {
+    match (edge, blend) {
+      (false, _) => e,
+      (true, false) => color,
+      (true, true) => mix(e, color, 128),
+    }
+  };
+
+  [
+    corner(d == b && b != f && d != h, d),
+    corner(b == f && b != d && f != h, f),
+    corner(d == h && d != b && h != f, d),
+    corner(h == f && h != d && f != b, f),
+    e,
+    e,
+    e,
+    e,
+    e,
+  ]
+}
+
+// AdvMAME3x on the 3x3 block `[a, b, c, d, e, f, g, h, i]` around `e`.
+fn scale3x(block: &[Color; 9]) -> [Color; 9] {
+  let [a, b, c, d, e, f, g, h, i]: [Color; 9] = *block;
+  let (top, left, right, bottom): (bool, bool, bool, bool) = (
+    d == b && b != f && d != h,
+    d == h && d != b && h != f,
+    b == f && b != d && f != h,
+    h == f && d != h && b != f,
+  );
+  let pick = |edge: bool, color: Color| -> Color {
+    if edge {
+      color
+    } else {
+      e
+    }
+  };
+
+  [
+    pick(top, d),
+    pick((top && e != c) || (right && e != a), b),
+    pick(right, f),
+    pick((top && e != g) || (left && e != a), d),
+    e,
+    pick((right && e != i) || (bottom && e != c), f),
+    pick(left, d),
+    pick((left && e != i) || (bottom && e != g), h),
+    pick(bottom, f),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const W: Color = (255, 255, 255);
+  const K: Color = (0, 0, 0);
+
+  // an unlit pixel in a lit corner - the filters fill in its bottom-left corner
+  const IMAGE: [Color; 4] = [W, K, W, W];
+
+  #[test]
+  fn test_nearest() {
+    assert_eq!(Filter::Nearest.apply(&IMAGE, 2, 2), IMAGE.to_vec());
+  }
+
+  #[test]
+  fn test_scale2x() {
+    assert_eq!(
+      Filter::Scale2x.apply(&IMAGE, 2, 2),
+      vec![W, W, K, K, W, W, W, K, W, W, W, W, W, W, W, W]
+    );
+  }
+
+  #[test]
+  fn test_scale3x() {
+    let output: Vec<Color> = Filter::Scale3x.apply(&IMAGE, 2, 2);
+
+    assert_eq!(output.len(), 36);
+    assert_eq!(output[15], W);
+    assert_eq!(output[16], K);
+  }
+
+  #[test]
+  fn test_hqx() {
+    assert_eq!(Filter::Hqx.apply(&IMAGE, 2, 2)[6], (128, 128, 128));
+  }
+
+  #[test]
+  fn test_scanlines() {
+    assert_eq!(
+      Filter::Scanlines.apply(&[W], 1, 1),
+      vec![W, W, (127, 127, 127), (127, 127, 127)]
+    );
+  }
+
+  #[test]
+  fn test_parse_filter() {
+    assert_eq!(Filter::parse("xbrz"), Err("Invalid Filter"));
+  }
+}
@@ -11,6 +11,8 @@ use crate::sdl2::AUDIO_S8;
 
 const FREQUENCY: i32 = 48000;
 const SAMPLES: usize = ((FREQUENCY as f32 / 50.0) / 100.0) as usize * 100;
+const TONE: u32 = 480;
+const VOLUME: u8 = 25;
 
 #[repr(C)]
 pub struct AudioBell<'a> {
@@ -21,6 +23,11 @@ pub struct AudioBell<'a> {
 
 impl<'a> AudioBell<'a> {
   pub fn new(token: &'a SDLToken) -> Result<Self, &'static str> {
+    Self::with_tone(token, TONE, VOLUME)
+  }
+
+  // Opens a bell playing a square wave of `tone` Hz with an amplitude of `volume`.
+  pub fn with_tone(token: &'a SDLToken, tone: u32, volume: u8) -> Result<Self, &'static str> {
     let device: AudioDevice = token.open_audio_device(SDL_AudioSpec {
       freq: FREQUENCY,
       format: AUDIO_S8 as u16,
@@ -34,9 +41,11 @@ impl<'a> AudioBell<'a> {
     })?;
 
     let mut buffer: [i8; SAMPLES] = [0; SAMPLES];
+    let period: usize = (FREQUENCY as u32 / tone.max(1)).max(2) as usize;
+    let volume: i8 = volume.min(i8::MAX as u8) as i8;
 
     for (index, byte) in buffer.iter_mut().enumerate() {
-      *byte = if index % 100 < 50 { 25 } else { -25 };
+      *byte = if index % period < period / 2 { volume } else { -volume };
     }
 
     Ok(Self {
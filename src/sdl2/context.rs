@@ -8,6 +8,7 @@ use crate::sdl2::Texture;
 pub struct SDL_Context<'a, 'b> {
   pub token: &'b SDLToken,
   pub renderer: &'b Renderer<'a>,
-  pub texture: &'b Texture<'a, 'b>,
+  pub texture: &'b Texture<'a, 'b>, // font
+  pub display: &'b Texture<'a, 'b>, // streaming texture the size of the hi-res display
   pub audio: &'b AudioBell<'a>,
 }
@@ -0,0 +1,51 @@
+use core::marker::PhantomData;
+
+use crate::sdl2::stringify;
+use crate::sdl2::SDLToken;
+use crate::sdl2::SDL_GameController;
+use crate::sdl2::SDL_GameControllerClose;
+use crate::sdl2::SDL_GameControllerGetJoystick;
+use crate::sdl2::SDL_GameControllerName;
+use crate::sdl2::SDL_JoystickID;
+use crate::sdl2::SDL_JoystickInstanceID;
+
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct GameController<'a> {
+  inner: *mut SDL_GameController,
+  _marker: PhantomData<&'a SDLToken>,
+}
+
+impl<'a> GameController<'a> {
+  #[inline]
+  pub(crate) const fn new(inner: *mut SDL_GameController) -> Self {
+    Self {
+      inner,
+      _marker: PhantomData,
+    }
+  }
+
+  // Returns the instance id used by controller events of this device.
+  pub fn id(&self) -> SDL_JoystickID {
+    unsafe { SDL_JoystickInstanceID(SDL_GameControllerGetJoystick(self.inner)) }
+  }
+
+  // Returns the device name - owned by SDL and freed when the controller is closed.
+  pub fn name(&self) -> &str {
+    unsafe {
+      let name: *const i8 = SDL_GameControllerName(self.inner);
+
+      if name.is_null() {
+        "Unknown Controller"
+      } else {
+        stringify(name)
+      }
+    }
+  }
+}
+
+impl<'a> Drop for GameController<'a> {
+  fn drop(&mut self) {
+    unsafe { SDL_GameControllerClose(self.inner) }
+  }
+}
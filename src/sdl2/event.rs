@@ -4,48 +4,110 @@ use crate::sdl2::SDLK_Keycode;
 use crate::sdl2::SDL_Button;
 use crate::sdl2::SDL_Event;
 use crate::sdl2::SDL_EventType;
+use crate::sdl2::SDL_FingerID;
+use crate::sdl2::SDL_GameControllerAxis;
+use crate::sdl2::SDL_GameControllerButton;
+use crate::sdl2::SDL_JoystickID;
+use crate::sdl2::SDL_Scancode;
+use crate::sdl2::SDL_WindowEventID;
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
 pub enum Event {
   Quit,
-  KeyDown(SDLK_Keycode),
-  KeyUp(SDLK_Keycode),
+  KeyDown(SDLK_Keycode, SDL_Scancode), // character and physical position of the key
+  KeyUp(SDLK_Keycode, SDL_Scancode),
   MouseDown(SDL_Button, i32, i32),
   MouseUp(SDL_Button, i32, i32),
   MouseMove(i32, i32),
   MouseWheel(i32, i32),
-  TouchDown(i32, i32),
-  TouchUp(i32, i32),
-  TouchMove(i32, i32),
+  TouchDown(SDL_FingerID, f32, f32), // finger and position normalized to 0..1
+  TouchUp(SDL_FingerID, f32, f32),
+  TouchMove(SDL_FingerID, f32, f32),
+  ControllerDown(SDL_JoystickID, SDL_GameControllerButton),
+  ControllerUp(SDL_JoystickID, SDL_GameControllerButton),
+  ControllerAxis(SDL_JoystickID, SDL_GameControllerAxis, i16),
+  ControllerAdded(i32), // joystick index - not an instance id
+  ControllerRemoved(SDL_JoystickID),
+  WindowResized(i32, i32), // new size of the window, also sent when entering or leaving fullscreen
 }
 
 impl Event {
   pub unsafe fn from_sdl_event(event: SDL_Event) -> Option<Self> {
     match transmute(event.type_) {
       SDL_EventType::QUIT => Some(Self::Quit),
-      SDL_EventType::KEYDOWN => Some(Self::KeyDown(transmute(event.key.keysym.sym))),
-      SDL_EventType::KEYUP => Some(Self::KeyUp(transmute(event.key.keysym.sym))),
+      SDL_EventType::WINDOWEVENT
+        if event.window.event == SDL_WindowEventID::SIZE_CHANGED as u8 =>
+      {
+        Some(Self::WindowResized(event.window.data1, event.window.data2))
+      }
+      SDL_EventType::KEYDOWN | SDL_EventType::KEYUP if event.key.repeat != 0 => None,
+      SDL_EventType::KEYDOWN => Some(Self::KeyDown(
+        transmute(event.key.keysym.sym),
+        event.key.keysym.scancode,
+      )),
+      SDL_EventType::KEYUP => Some(Self::KeyUp(
+        transmute(event.key.keysym.sym),
+        event.key.keysym.scancode,
+      )),
       SDL_EventType::MOUSEBUTTONDOWN => {
-        Some(Self::MouseDown(transmute(event.button.button as u32), 0, 0))
+        Some(Self::MouseDown(
+          transmute(event.button.button as u32),
+          event.button.x,
+          event.button.y,
+        ))
       }
       SDL_EventType::MOUSEBUTTONUP => {
-        Some(Self::MouseUp(transmute(event.button.button as u32), 0, 0))
+        Some(Self::MouseUp(
+          transmute(event.button.button as u32),
+          event.button.x,
+          event.button.y,
+        ))
       }
       SDL_EventType::MOUSEMOTION => Some(Self::MouseMove(event.motion.x, event.motion.y)),
       SDL_EventType::MOUSEWHEEL => Some(Self::MouseWheel(event.wheel.x, event.wheel.y)),
       SDL_EventType::FINGERDOWN => Some(Self::TouchDown(
-        event.tfinger.x as i32,
-        event.tfinger.y as i32,
+        event.tfinger.fingerId,
+        event.tfinger.x,
+        event.tfinger.y,
       )),
       SDL_EventType::FINGERUP => Some(Self::TouchUp(
-        event.tfinger.x as i32,
-        event.tfinger.y as i32,
+        event.tfinger.fingerId,
+        event.tfinger.x,
+        event.tfinger.y,
       )),
       SDL_EventType::FINGERMOTION => Some(Self::TouchMove(
-        event.tfinger.x as i32,
-        event.tfinger.y as i32,
+        event.tfinger.fingerId,
+        event.tfinger.x,
+        event.tfinger.y,
+      )),
+      SDL_EventType::CONTROLLERBUTTONDOWN | SDL_EventType::CONTROLLERBUTTONUP
+        if event.cbutton.button > SDL_GameControllerButton::TOUCHPAD as u8 =>
+      {
+        None
+      }
+      SDL_EventType::CONTROLLERBUTTONDOWN => Some(Self::ControllerDown(
+        event.cbutton.which,
+        transmute::<u8, SDL_GameControllerButton>(event.cbutton.button),
       )),
+      SDL_EventType::CONTROLLERBUTTONUP => Some(Self::ControllerUp(
+        event.cbutton.which,
+        transmute::<u8, SDL_GameControllerButton>(event.cbutton.button),
+      )),
+      SDL_EventType::CONTROLLERAXISMOTION
+        if event.caxis.axis > SDL_GameControllerAxis::TRIGGERRIGHT as u8 =>
+      {
+        None
+      }
+      SDL_EventType::CONTROLLERAXISMOTION => Some(Self::ControllerAxis(
+        event.caxis.which,
+        transmute::<u8, SDL_GameControllerAxis>(event.caxis.axis),
+        event.caxis.value,
+      )),
+      SDL_EventType::CONTROLLERDEVICEADDED => Some(Self::ControllerAdded(event.cdevice.which)),
+      SDL_EventType::CONTROLLERDEVICEREMOVED => {
+        Some(Self::ControllerRemoved(event.cdevice.which))
+      }
       _ => None,
     }
   }
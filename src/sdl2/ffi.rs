@@ -98,6 +98,65 @@ pub enum SDL_Button {
   X2 = SDL_BUTTON_X2,
 }
 
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum SDL_GameControllerButton {
+  A = 0,
+  B = 1,
+  X = 2,
+  Y = 3,
+  BACK = 4,
+  GUIDE = 5,
+  START = 6,
+  LEFTSTICK = 7,
+  RIGHTSTICK = 8,
+  LEFTSHOULDER = 9,
+  RIGHTSHOULDER = 10,
+  DPAD_UP = 11,
+  DPAD_DOWN = 12,
+  DPAD_LEFT = 13,
+  DPAD_RIGHT = 14,
+  MISC1 = 15,
+  PADDLE1 = 16,
+  PADDLE2 = 17,
+  PADDLE3 = 18,
+  PADDLE4 = 19,
+  TOUCHPAD = 20,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum SDL_GameControllerAxis {
+  LEFTX = 0,
+  LEFTY = 1,
+  RIGHTX = 2,
+  RIGHTY = 3,
+  TRIGGERLEFT = 4,
+  TRIGGERRIGHT = 5,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SDL_WindowEventID {
+  NONE = 0,
+  SHOWN = 1,
+  HIDDEN = 2,
+  EXPOSED = 3,
+  MOVED = 4,
+  RESIZED = 5,
+  SIZE_CHANGED = 6,
+  MINIMIZED = 7,
+  MAXIMIZED = 8,
+  RESTORED = 9,
+  ENTER = 10,
+  LEAVE = 11,
+  FOCUS_GAINED = 12,
+  FOCUS_LOST = 13,
+  CLOSE = 14,
+  TAKE_FOCUS = 15,
+  HIT_TEST = 16,
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 #[repr(u32)]
 pub enum GLattr {
@@ -156,6 +215,18 @@ pub struct SDL_Texture {
   _unused: [u8; 0],
 }
 
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SDL_GameController {
+  _unused: [u8; 0],
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SDL_Joystick {
+  _unused: [u8; 0],
+}
+
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct SDL_BlitMap {
@@ -1280,6 +1351,16 @@ extern "C" {
 
   pub fn SDL_DestroyWindow(window: *mut SDL_Window);
 
+  pub fn SDL_GetWindowSize(window: *mut SDL_Window, w: *mut i32, h: *mut i32);
+
+  pub fn SDL_SetWindowSize(window: *mut SDL_Window, w: i32, h: i32);
+
+  pub fn SDL_SetWindowMinimumSize(window: *mut SDL_Window, min_w: i32, min_h: i32);
+
+  pub fn SDL_SetWindowFullscreen(window: *mut SDL_Window, flags: u32) -> i32;
+
+  pub fn SDL_SetWindowTitle(window: *mut SDL_Window, title: *const i8);
+
   pub fn SDL_PollEvent(event: *mut SDL_Event) -> i32;
 
   // ===========================================================================
@@ -1341,6 +1422,32 @@ extern "C" {
 
   pub fn SDL_DestroyTexture(texture: *mut SDL_Texture);
 
+  pub fn SDL_SetTextureColorMod(texture: *mut SDL_Texture, r: u8, g: u8, b: u8) -> i32;
+
+  pub fn SDL_QueryTexture(
+    texture: *mut SDL_Texture,
+    format: *mut u32,
+    access: *mut i32,
+    w: *mut i32,
+    h: *mut i32,
+  ) -> i32;
+
+  pub fn SDL_UpdateTexture(
+    texture: *mut SDL_Texture,
+    rect: *const SDL_Rect,
+    pixels: *const c_void,
+    pitch: i32,
+  ) -> i32;
+
+  pub fn SDL_LockTexture(
+    texture: *mut SDL_Texture,
+    rect: *const SDL_Rect,
+    pixels: *mut *mut c_void,
+    pitch: *mut i32,
+  ) -> i32;
+
+  pub fn SDL_UnlockTexture(texture: *mut SDL_Texture);
+
   pub fn SDL_FreeSurface(surface: *mut SDL_Surface);
 
   pub fn SDL_LoadBMP_RW(src: *mut SDL_RWops, freesrc: i32) -> *mut SDL_Surface;
@@ -1367,6 +1474,24 @@ extern "C" {
 
   pub fn SDL_Delay(millis: u32);
 
+  // ===========================================================================
+  // Game Controller
+  // ===========================================================================
+
+  pub fn SDL_NumJoysticks() -> i32;
+
+  pub fn SDL_IsGameController(joystick_index: i32) -> i32;
+
+  pub fn SDL_GameControllerOpen(joystick_index: i32) -> *mut SDL_GameController;
+
+  pub fn SDL_GameControllerClose(gamecontroller: *mut SDL_GameController);
+
+  pub fn SDL_GameControllerName(gamecontroller: *mut SDL_GameController) -> *const i8;
+
+  pub fn SDL_GameControllerGetJoystick(gamecontroller: *mut SDL_GameController) -> *mut SDL_Joystick;
+
+  pub fn SDL_JoystickInstanceID(joystick: *mut SDL_Joystick) -> SDL_JoystickID;
+
   // ===========================================================================
   // Audio
   // ===========================================================================
@@ -11,6 +11,7 @@ mod macros;
 mod audio;
 mod bell;
 mod context;
+mod controller;
 mod event;
 mod ffi;
 mod opengl;
@@ -24,6 +25,7 @@ mod window;
 pub use self::audio::*;
 pub use self::bell::*;
 pub use self::context::*;
+pub use self::controller::*;
 pub use self::event::*;
 pub use self::ffi::*;
 pub use self::opengl::*;
@@ -1,8 +1,18 @@
+use core::ffi::c_void;
 use core::marker::PhantomData;
+use core::ptr::null;
+use core::ptr::null_mut;
+use core::slice::from_raw_parts_mut;
 
+use crate::sdl2::error;
 use crate::sdl2::Renderer;
 use crate::sdl2::SDL_DestroyTexture;
+use crate::sdl2::SDL_LockTexture;
+use crate::sdl2::SDL_QueryTexture;
+use crate::sdl2::SDL_SetTextureColorMod;
 use crate::sdl2::SDL_Texture;
+use crate::sdl2::SDL_UnlockTexture;
+use crate::sdl2::SDL_UpdateTexture;
 
 #[derive(Debug)]
 #[repr(transparent)]
@@ -24,6 +34,65 @@ impl<'a, 'b> Texture<'a, 'b> {
   pub(crate) fn as_ptr(&self) -> *mut SDL_Texture {
     self.inner
   }
+
+  #[inline]
+  pub fn color(&self, r: u8, g: u8, b: u8) {
+    try_sdl2!(SDL_SetTextureColorMod, self.inner, r, g, b);
+  }
+
+  pub fn size(&self) -> (i32, i32) {
+    let mut size: (i32, i32) = (0, 0);
+
+    try_sdl2!(
+      SDL_QueryTexture,
+      self.inner,
+      null_mut(),
+      null_mut(),
+      &mut size.0,
+      &mut size.1
+    );
+
+    size
+  }
+
+  // Replaces all pixels of the texture - `pitch` is the length of a row in bytes.
+  pub fn update(&self, pixels: &[u8], pitch: i32) -> Result<(), &'static str> {
+    if pitch <= 0 || pixels.len() < (pitch * self.size().1) as usize {
+      return Err("Invalid Texture Data");
+    }
+
+    let result: i32 =
+      unsafe { SDL_UpdateTexture(self.inner, null(), pixels.as_ptr() as *const c_void, pitch) };
+
+    if result != 0 {
+      Err(error())
+    } else {
+      Ok(())
+    }
+  }
+
+  // Passes the pixels of a streaming texture and the length of a row in bytes
+  // to `f`. The previous contents are lost, so `f` must write every pixel.
+  pub fn with_lock<F: FnOnce(&mut [u8], usize)>(&self, f: F) -> Result<(), &'static str> {
+    let rows: i32 = self.size().1;
+    let mut pixels: *mut c_void = null_mut();
+    let mut pitch: i32 = 0;
+
+    if unsafe { SDL_LockTexture(self.inner, null(), &mut pixels, &mut pitch) } != 0 {
+      return Err(error());
+    }
+
+    f(
+      unsafe { from_raw_parts_mut(pixels as *mut u8, (pitch * rows) as usize) },
+      pitch as usize,
+    );
+
+    unsafe {
+      SDL_UnlockTexture(self.inner);
+    }
+
+    Ok(())
+  }
 }
 
 impl<'a, 'b> Drop for Texture<'a, 'b> {
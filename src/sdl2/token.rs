@@ -7,13 +7,17 @@ use core::sync::atomic::Ordering;
 use crate::sdl2::error;
 use crate::sdl2::AudioDevice;
 use crate::sdl2::Event;
+use crate::sdl2::GameController;
 use crate::sdl2::SDL_AudioSpec;
 use crate::sdl2::SDL_CreateWindow;
 use crate::sdl2::SDL_Delay;
 use crate::sdl2::SDL_Event;
+use crate::sdl2::SDL_GameController;
+use crate::sdl2::SDL_GameControllerOpen;
 use crate::sdl2::SDL_GetNumAudioDevices;
 use crate::sdl2::SDL_GetTicks;
 use crate::sdl2::SDL_Init;
+use crate::sdl2::SDL_IsGameController;
 use crate::sdl2::SDL_OpenAudioDevice;
 use crate::sdl2::SDL_PollEvent;
 use crate::sdl2::SDL_Quit;
@@ -90,6 +94,21 @@ impl SDLToken {
     }
   }
 
+  // Opens the joystick at `index` (not its instance id) as a game controller.
+  pub fn open_controller(&self, index: i32) -> Result<GameController<'_>, &'static str> {
+    if unsafe { SDL_IsGameController(index) } == 0 {
+      return Err("Unsupported Game Controller");
+    }
+
+    let controller: *mut SDL_GameController = unsafe { SDL_GameControllerOpen(index) };
+
+    if controller.is_null() {
+      Err(error())
+    } else {
+      Ok(GameController::new(controller))
+    }
+  }
+
   pub fn poll(&self) -> Option<Event> {
     let mut event: SDL_Event = SDL_Event { type_: 0 };
 
@@ -9,10 +9,16 @@ use crate::sdl2::SDL_CreateRenderer;
 use crate::sdl2::SDL_DestroyWindow;
 use crate::sdl2::SDL_GLContext;
 use crate::sdl2::SDL_GL_CreateContext;
+use crate::sdl2::SDL_GetWindowSize;
 use crate::sdl2::SDL_Renderer;
+use crate::sdl2::SDL_SetWindowFullscreen;
+use crate::sdl2::SDL_SetWindowMinimumSize;
+use crate::sdl2::SDL_SetWindowSize;
+use crate::sdl2::SDL_SetWindowTitle;
 use crate::sdl2::SDL_Window;
 use crate::sdl2::SDL_WINDOWPOS_CENTERED_MASK;
 use crate::sdl2::SDL_WINDOWPOS_UNDEFINED_MASK;
+use crate::sdl2::WindowFlags;
 
 #[derive(Debug)]
 #[repr(transparent)]
@@ -38,6 +44,49 @@ impl<'a> Window<'a> {
     self.inner
   }
 
+  pub fn size(&self) -> (i32, i32) {
+    let mut size: (i32, i32) = (0, 0);
+
+    unsafe {
+      SDL_GetWindowSize(self.inner, &mut size.0, &mut size.1);
+    }
+
+    size
+  }
+
+  #[inline]
+  pub fn set_size(&self, w: i32, h: i32) {
+    unsafe {
+      SDL_SetWindowSize(self.inner, w, h);
+    }
+  }
+
+  #[inline]
+  pub fn set_minimum_size(&self, w: i32, h: i32) {
+    unsafe {
+      SDL_SetWindowMinimumSize(self.inner, w, h);
+    }
+  }
+
+  // Sets the title of the window - `title` must be nul-terminated.
+  #[inline]
+  pub fn set_title(&self, title: &str) {
+    unsafe {
+      SDL_SetWindowTitle(self.inner, title.as_ptr() as *const i8);
+    }
+  }
+
+  // Switches between a window and a borderless window covering the desktop.
+  pub fn set_fullscreen(&self, fullscreen: bool) {
+    let flags: WindowFlags = if fullscreen {
+      WindowFlags::FULLSCREEN_DESKTOP
+    } else {
+      WindowFlags::empty()
+    };
+
+    try_sdl2!(SDL_SetWindowFullscreen, self.inner, flags.bits());
+  }
+
   pub fn try_into_renderer(
     self,
     flags: RendererFlags,
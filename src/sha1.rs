@@ -0,0 +1,71 @@
+// SHA-1 digest of `data` as a lowercase hex string - used to identify ROMs.
+pub fn sha1(data: &[u8]) -> String {
+  let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+  let mut message: Vec<u8> = data.to_vec();
+
+  message.push(0x80);
+
+  while message.len() % 64 != 56 {
+    message.push(0x00);
+  }
+
+  message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+  for block in message.chunks(64) {
+    let mut words: [u32; 80] = [0; 80];
+
+    for (index, word) in block.chunks(4).enumerate() {
+      words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+
+    for index in 16..80 {
+      words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16])
+        .rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e]: [u32; 5] = state;
+
+    for (index, word) in words.iter().enumerate() {
+      let (f, k): (u32, u32) = match index {
+        0..=19 => ((b & c) | (!b & d), 0x5A827999),
+        20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+        40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+        _ => (b ^ c ^ d, 0xCA62C1D6),
+      };
+
+      let temp: u32 = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(*word);
+
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = temp;
+    }
+
+    for (value, add) in state.iter_mut().zip([a, b, c, d, e].iter()) {
+      *value = value.wrapping_add(*add);
+    }
+  }
+
+  state.iter().map(|value| format!("{:08x}", value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sha1() {
+    assert_eq!(sha1(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+      sha1(&[b'a'; 1000]),
+      "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+    );
+  }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+
+use crate::chip8::RAM;
+use crate::instruction::Instruction;
+use crate::number::parse_number;
+
+// Memory region shown as a live watch panel.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Monitor {
+  pub name: String,
+  pub address: u16,
+  pub length: u16,
+}
+
+// Symbol map loaded alongside a ROM.
+//
+// Each line is either a label (`0x2A4 draw_player`), an Octo-style breakpoint
+// (`:breakpoint name address`) or an Octo-style monitor (`:monitor address length`).
+// Addresses of breakpoints and monitors may refer to labels defined earlier.
+// Empty lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+  labels: BTreeMap<u16, String>,
+  breakpoints: Vec<(u16, String)>,
+  monitors: Vec<Monitor>,
+}
+
+impl Symbols {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn load(path: &str) -> Result<Self, &'static str> {
+    read_to_string(path)
+      .map_err(|_| "Invalid Symbol File")
+      .and_then(|input| Self::parse(&input))
+  }
+
+  pub fn parse(input: &str) -> Result<Self, &'static str> {
+    let mut this: Self = Self::new();
+
+    for line in input.lines().map(str::trim) {
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let words: Vec<&str> = line.split_whitespace().collect();
+
+      match words.as_slice() {
+        [":breakpoint", name, address] => {
+          let address: u16 = this.resolve(address).ok_or("Invalid Breakpoint Address")?;
+          this.breakpoints.push((address, name.to_string()));
+        }
+        [":monitor", address, length] => {
+          let name: String = address.to_string();
+          let address: u16 = this.resolve(address).ok_or("Invalid Monitor Address")?;
+          let length: u16 = parse_number(length)
+            .filter(|length| address as usize + *length as usize <= RAM)
+            .ok_or("Invalid Monitor Length")?;
+
+          this.monitors.push(Monitor {
+            name,
+            address,
+            length,
+          });
+        }
+        [address, label] => {
+          let address: u16 = parse_number(address).ok_or("Invalid Label Address")?;
+          this.labels.insert(address, label.to_string());
+        }
+        _ => return Err("Invalid Symbol Entry"),
+      }
+    }
+
+    Ok(this)
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.labels.is_empty() && self.breakpoints.is_empty() && self.monitors.is_empty()
+  }
+
+  #[inline]
+  pub fn label(&self, address: u16) -> Option<&str> {
+    self.labels.get(&address).map(String::as_str)
+  }
+
+  pub fn address(&self, label: &str) -> Option<u16> {
+    self
+      .labels
+      .iter()
+      .find(|(_, name)| *name == label)
+      .map(|(address, _)| *address)
+  }
+
+  #[inline]
+  pub fn breakpoints(&self) -> &[(u16, String)] {
+    &self.breakpoints
+  }
+
+  #[inline]
+  pub fn is_breakpoint(&self, address: u16) -> bool {
+    self
+      .breakpoints
+      .iter()
+      .any(|(breakpoint, _)| *breakpoint == address)
+  }
+
+  #[inline]
+  pub fn monitors(&self) -> &[Monitor] {
+    &self.monitors
+  }
+
+  // Describes an address relative to the closest preceding label, ie. `main+0x4`.
+  pub fn locate(&self, address: u16) -> String {
+    match self.labels.range(..=address).next_back() {
+      Some((base, label)) if *base == address => label.clone(),
+      Some((base, label)) => format!("{}+{:#X}", label, address - base),
+      None => format!("{:#06X}", address),
+    }
+  }
+
+  // Disassembles an opcode, naming its address operand if a label exists for it.
+  pub fn disassemble(&self, instruction: &Instruction, opcode: u16) -> String {
+    match self.label(nnn!(opcode)) {
+      Some(label) if instruction.desc.contains("addr") => {
+        instruction.disassemble_with(opcode, label)
+      }
+      _ => instruction.disassemble(opcode),
+    }
+  }
+
+  fn resolve(&self, address: &str) -> Option<u16> {
+    parse_number(address).or_else(|| self.address(address))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const INPUT: &str = "
+    # labels exported from the assembler
+    0x200 main
+    0x2A4 draw_player
+
+    :breakpoint collision 0x2B0
+    :breakpoint redraw draw_player
+    :monitor 0x3F0 8
+    :monitor draw_player 3
+  ";
+
+  #[test]
+  fn test_parse() {
+    let symbols: Symbols = Symbols::parse(INPUT).unwrap();
+
+    assert_eq!(symbols.label(0x2A4), Some("draw_player"));
+    assert_eq!(symbols.address("main"), Some(0x200));
+    assert!(symbols.is_breakpoint(0x2B0));
+    assert!(symbols.is_breakpoint(0x2A4));
+    assert_eq!(symbols.monitors()[0].address, 0x3F0);
+    assert_eq!(symbols.monitors()[0].length, 8);
+    assert_eq!(symbols.monitors()[1].name, "draw_player");
+    assert!(Symbols::parse("0x200").is_err());
+    assert!(Symbols::parse(":monitor unknown 2").is_err());
+    assert_eq!(
+      Symbols::parse(":monitor 0xFFE 1").map(|symbols| symbols.monitors().len()),
+      Ok(1)
+    );
+    assert!(Symbols::parse(":monitor 0xFFE 2").is_err());
+  }
+
+  #[test]
+  fn test_locate() {
+    let symbols: Symbols = Symbols::parse(INPUT).unwrap();
+
+    assert_eq!(symbols.locate(0x1FE), "0x01FE");
+    assert_eq!(symbols.locate(0x200), "main");
+    assert_eq!(symbols.locate(0x206), "main+0x6");
+  }
+
+  #[test]
+  fn test_disassemble() {
+    let symbols: Symbols = Symbols::parse(INPUT).unwrap();
+    let call: &Instruction = Instruction::find(0x22A4).unwrap();
+    let load: &Instruction = Instruction::find(0x6200).unwrap();
+
+    assert_eq!(symbols.disassemble(call, 0x22A4), "CALL    draw_player");
+    assert_eq!(symbols.disassemble(call, 0x22A6), "CALL    02A6");
+    assert_eq!(symbols.disassemble(load, 0x6200), "LD      V2, 00");
+  }
+}
@@ -0,0 +1,463 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FResult;
+use core::ops::RangeInclusive;
+use std::fs::File;
+use std::io::stdout;
+use std::io::BufWriter;
+use std::io::Write;
+
+use crate::chip8::Chip8;
+use crate::instruction::Instruction;
+use crate::number::parse_hex;
+
+// Writes one line per executed instruction, describing the machine state
+// *before* the instruction runs:
+//
+//   CYCLE      PC   OP   MNEMONIC             V 00 .. 0F         I    SP DT ST
+//   0000000012 0204 A22A LD      I, 022A      V 00 .. 00 I 0000 SP 00 DT 00 ST 00
+//
+// All numbers except the decimal cycle count are upper-case hexadecimal.
+// Fields are separated by whitespace - the mnemonic is everything between the
+// opcode and the `V` marker. Memory is not traced, so `m<addr>` fields of a
+// `TraceMap` can only be compared between two mapped traces.
+pub struct Tracer {
+  output: Box<dyn Write>,
+  range: RangeInclusive<u16>,
+}
+
+impl Tracer {
+  pub fn new(output: Box<dyn Write>) -> Self {
+    Self {
+      output,
+      range: 0x0000..=0xFFFF,
+    }
+  }
+
+  pub fn stdout() -> Self {
+    Self::new(Box::new(stdout()))
+  }
+
+  pub fn create(path: &str) -> Result<Self, &'static str> {
+    File::create(path)
+      .map(|file| Self::new(Box::new(BufWriter::new(file))))
+      .map_err(|_| "Invalid Trace File")
+  }
+
+  // Restricts tracing to instructions whose address falls within `range`.
+  pub fn range(mut self, range: RangeInclusive<u16>) -> Self {
+    self.range = range;
+    self
+  }
+
+  // Parses an address range in the form `START-END`, ie. `0x200-0x2FF`.
+  pub fn parse_range(input: &str) -> Result<RangeInclusive<u16>, &'static str> {
+    let mut parts = input.splitn(2, '-').map(parse_hex);
+
+    match (parts.next(), parts.next()) {
+      (Some(Some(start)), Some(Some(end))) if start <= end => Ok(start..=end),
+      _ => Err("Invalid Trace Range"),
+    }
+  }
+
+  pub(crate) fn record(&mut self, chip8: &Chip8, opcode: u16) {
+    if self.range.contains(&chip8.pc) {
+      // A failing trace sink must not take the interpreter down with it
+      let _ = writeln!(self.output, "{}", Self::format(chip8, opcode));
+    }
+  }
+
+  pub(crate) fn format(chip8: &Chip8, opcode: u16) -> String {
+    let mnemonic: String = match Instruction::find(opcode) {
+      Some(instruction) => instruction.disassemble(opcode),
+      None => "???".to_string(),
+    };
+
+    let registers: Vec<String> = chip8
+      .reg_v
+      .iter()
+      .map(|value| format!("{:02X}", value))
+      .collect();
+
+    format!(
+      "{cycle:010} {pc:04X} {opcode:04X} {mnemonic:<20} V {registers} I {i:04X} SP {sp:02X} DT {dt:02X} ST {st:02X}",
+      cycle = chip8.cycles,
+      pc = chip8.pc,
+      opcode = opcode,
+      mnemonic = mnemonic,
+      registers = registers.join(" "),
+      i = chip8.reg_i,
+      sp = chip8.sp,
+      dt = chip8.delay,
+      st = chip8.sound,
+    )
+  }
+}
+
+// A single comparable value of a trace line.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum TraceField {
+  Pc,
+  Op,
+  V(u8),
+  I,
+  Sp,
+  Dt,
+  St,
+  Mem(u16),
+}
+
+impl TraceField {
+  // Parses a column name: `pc`, `op`, `v0`-`vf`, `i`, `sp`, `dt`, `st` or `m<addr>`.
+  pub fn parse(input: &str) -> Option<Self> {
+    match input.to_ascii_lowercase().as_str() {
+      "pc" => Some(Self::Pc),
+      "op" => Some(Self::Op),
+      "i" => Some(Self::I),
+      "sp" => Some(Self::Sp),
+      "dt" => Some(Self::Dt),
+      "st" => Some(Self::St),
+      name if name.len() == 2 && name.starts_with('v') => {
+        u8::from_str_radix(&name[1..], 16).ok().map(Self::V)
+      }
+      name if name.starts_with('m') => parse_hex(&name[1..]).map(Self::Mem),
+      _ => None,
+    }
+  }
+
+  fn width(self) -> usize {
+    match self {
+      Self::Pc | Self::Op | Self::I => 4,
+      _ => 2,
+    }
+  }
+}
+
+impl Display for TraceField {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    match self {
+      Self::Pc => f.write_str("PC"),
+      Self::Op => f.write_str("OP"),
+      Self::V(index) => write!(f, "V{:X}", index),
+      Self::I => f.write_str("I"),
+      Self::Sp => f.write_str("SP"),
+      Self::Dt => f.write_str("DT"),
+      Self::St => f.write_str("ST"),
+      Self::Mem(address) => write!(f, "[{:04X}]", address),
+    }
+  }
+}
+
+// A parsed trace line - only the fields present in both records are compared,
+// which skips memory fields when one side was written by `Tracer`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceRecord {
+  pub cycle: Option<u64>,
+  pub values: Vec<(TraceField, u16)>,
+}
+
+impl TraceRecord {
+  // Parses a line in the format written by `Tracer`.
+  pub fn parse(line: &str) -> Result<Self, &'static str> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    // cycle, pc, opcode, mnemonic (1+ words) and the 25 trailing register tokens
+    if tokens.len() < 3 + 1 + 25 {
+      return Err("Invalid Trace Line");
+    }
+
+    let state: &[&str] = &tokens[tokens.len() - 25..];
+
+    if state[0] != "V"
+      || state[17] != "I"
+      || state[19] != "SP"
+      || state[21] != "DT"
+      || state[23] != "ST"
+    {
+      return Err("Invalid Trace Line");
+    }
+
+    let mut values: Vec<(TraceField, u16)> = vec![
+      (TraceField::Pc, parse_value(tokens[1])?),
+      (TraceField::Op, parse_value(tokens[2])?),
+    ];
+
+    for (index, token) in state[1..17].iter().enumerate() {
+      values.push((TraceField::V(index as u8), parse_value(token)?));
+    }
+
+    values.push((TraceField::I, parse_value(state[18])?));
+    values.push((TraceField::Sp, parse_value(state[20])?));
+    values.push((TraceField::Dt, parse_value(state[22])?));
+    values.push((TraceField::St, parse_value(state[24])?));
+
+    Ok(Self {
+      cycle: tokens[0].parse().ok(),
+      values,
+    })
+  }
+
+  pub fn get(&self, field: TraceField) -> Option<u16> {
+    self
+      .values
+      .iter()
+      .find(|(name, _)| *name == field)
+      .map(|(_, value)| *value)
+  }
+
+  // Returns every field present in both records whose values differ.
+  pub fn diff(&self, other: &Self) -> Vec<TraceDiff> {
+    self
+      .values
+      .iter()
+      .filter_map(|(field, value)| match other.get(*field) {
+        Some(other) if other != *value => Some(TraceDiff {
+          field: *field,
+          left: *value,
+          right: other,
+        }),
+        _ => None,
+      })
+      .collect()
+  }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct TraceDiff {
+  pub field: TraceField,
+  pub left: u16,
+  pub right: u16,
+}
+
+impl Display for TraceDiff {
+  fn fmt(&self, f: &mut Formatter) -> FResult {
+    write!(
+      f,
+      "{field}: {left:0width$X} != {right:0width$X}",
+      field = self.field,
+      left = self.left,
+      right = self.right,
+      width = self.field.width(),
+    )
+  }
+}
+
+// A step of two traces walked side by side - records of the same cycle, or a
+// record of a cycle the other trace skipped.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum TracePair {
+  Both(usize, usize),
+  Left(usize),
+  Right(usize),
+}
+
+impl TracePair {
+  // Pairs the records of two traces by cycle, or by position where a record has
+  // no cycle. Stops at the end of the shorter trace.
+  pub fn align(left: &[TraceRecord], right: &[TraceRecord]) -> Vec<Self> {
+    let mut pairs: Vec<Self> = Vec::with_capacity(left.len().min(right.len()));
+    let (mut lhs, mut rhs): (usize, usize) = (0, 0);
+
+    while lhs < left.len() && rhs < right.len() {
+      let pair: Self = match (left[lhs].cycle, right[rhs].cycle) {
+        (Some(left), Some(right)) if left < right => Self::Left(lhs),
+        (Some(left), Some(right)) if left > right => Self::Right(rhs),
+        _ => Self::Both(lhs, rhs),
+      };
+
+      match pair {
+        Self::Both(..) => {
+          lhs += 1;
+          rhs += 1;
+        }
+        Self::Left(_) => lhs += 1,
+        Self::Right(_) => rhs += 1,
+      }
+
+      pairs.push(pair);
+    }
+
+    pairs
+  }
+}
+
+// Column mapping for traces written by other emulators.
+//
+// The spec is a comma-separated list of `field=column` pairs, ie.
+// `cycle=0,pc=1,op=2,v0=4,i=20,m300=24`. Columns are zero-based and split on
+// whitespace and commas. Values are hexadecimal (the cycle is decimal) and may
+// carry a `NAME:`/`NAME=` label and a `0x` or `$` prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceMap {
+  cycle: Option<usize>,
+  columns: Vec<(TraceField, usize)>,
+}
+
+impl TraceMap {
+  pub fn parse(spec: &str) -> Result<Self, &'static str> {
+    let mut this: Self = Self::default();
+
+    for entry in spec
+      .split(',')
+      .map(str::trim)
+      .filter(|entry| !entry.is_empty())
+    {
+      let mut parts = entry.splitn(2, '=');
+      let name: &str = parts.next().unwrap_or_default();
+      let column: usize = parts
+        .next()
+        .and_then(|column| column.parse().ok())
+        .ok_or("Invalid Trace Map Column")?;
+
+      if name.eq_ignore_ascii_case("cycle") {
+        this.cycle = Some(column);
+      } else {
+        this.columns.push((
+          TraceField::parse(name).ok_or("Invalid Trace Map Field")?,
+          column,
+        ));
+      }
+    }
+
+    if this.columns.is_empty() {
+      Err("Empty Trace Map")
+    } else {
+      Ok(this)
+    }
+  }
+
+  pub fn record(&self, line: &str) -> Result<TraceRecord, &'static str> {
+    let tokens: Vec<&str> = line
+      .split(|char: char| char.is_whitespace() || char == ',')
+      .filter(|token| !token.is_empty())
+      .collect();
+
+    let column = |index: usize| tokens.get(index).map(|token| strip_label(token));
+
+    let cycle: Option<u64> = match self.cycle {
+      Some(index) => Some(
+        column(index)
+          .and_then(|token| token.parse().ok())
+          .ok_or("Invalid Trace Cycle")?,
+      ),
+      None => None,
+    };
+
+    let mut values: Vec<(TraceField, u16)> = Vec::with_capacity(self.columns.len());
+
+    for (field, index) in self.columns.iter() {
+      values.push((
+        *field,
+        parse_value(column(*index).ok_or("Missing Trace Column")?)?,
+      ));
+    }
+
+    Ok(TraceRecord { cycle, values })
+  }
+}
+
+fn strip_label(token: &str) -> &str {
+  match token.rfind([':', '=']) {
+    Some(index) => &token[index + 1..],
+    None => token,
+  }
+}
+
+fn parse_value(input: &str) -> Result<u16, &'static str> {
+  parse_hex(input.strip_prefix('$').unwrap_or(input)).ok_or("Invalid Trace Value")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.load_bytes(&[0x6A, 0x02, 0xA2, 0x2A], false).unwrap();
+    chip8.step();
+
+    assert_eq!(
+      Tracer::format(&chip8, 0xA22A),
+      "0000000001 0202 A22A LD      I, 022A      V 00 00 00 00 00 00 00 00 00 00 02 00 00 00 00 00 I 0000 SP 00 DT 00 ST 00"
+    );
+  }
+
+  #[test]
+  fn test_record() {
+    let mut chip8: Chip8 = Chip8::new();
+
+    chip8.load_bytes(&[0x6A, 0x02, 0xA2, 0x2A], false).unwrap();
+    chip8.step();
+
+    let left: TraceRecord = TraceRecord::parse(&Tracer::format(&chip8, 0xA22A)).unwrap();
+
+    assert_eq!(left.cycle, Some(1));
+    assert_eq!(left.get(TraceField::Pc), Some(0x202));
+    assert_eq!(left.get(TraceField::V(0xA)), Some(0x02));
+
+    let map: TraceMap = TraceMap::parse("cycle=0,pc=1,op=2,va=3,i=4,m300=5").unwrap();
+    let right: TraceRecord = map.record("1, PC:$0202 OP:A22A VA=03 I=0x0000 07").unwrap();
+
+    assert_eq!(right.get(TraceField::Mem(0x300)), Some(0x07));
+    assert_eq!(left.get(TraceField::Mem(0x300)), None);
+    assert_eq!(
+      left.diff(&right),
+      vec![TraceDiff {
+        field: TraceField::V(0xA),
+        left: 0x02,
+        right: 0x03,
+      }]
+    );
+    assert_eq!(left.diff(&right)[0].to_string(), "VA: 02 != 03");
+
+    // Memory is only compared between mapped traces
+    let other: TraceRecord = map.record("1, PC:$0202 OP:A22A VA=03 I=0x0000 08").unwrap();
+
+    assert_eq!(right.diff(&other)[0].to_string(), "[0300]: 07 != 08");
+    assert!(TraceRecord::parse("1 0200 00E0 CLS").is_err());
+    assert!(TraceMap::parse("pc=x").is_err());
+  }
+
+  #[test]
+  fn test_align() {
+    let map: TraceMap = TraceMap::parse("cycle=0,pc=1").unwrap();
+    let trace = |cycles: &[u64]| -> Vec<TraceRecord> {
+      cycles
+        .iter()
+        .map(|cycle| map.record(&format!("{} 0200", cycle)).unwrap())
+        .collect()
+    };
+
+    // The right trace dropped cycle 3 and the left one cycle 5
+    assert_eq!(
+      TracePair::align(&trace(&[1, 2, 3, 4, 6]), &trace(&[1, 2, 4, 5, 6, 7])),
+      vec![
+        TracePair::Both(0, 0),
+        TracePair::Both(1, 1),
+        TracePair::Left(2),
+        TracePair::Both(3, 2),
+        TracePair::Right(3),
+        TracePair::Both(4, 4),
+      ]
+    );
+
+    // Records without a cycle are paired in order
+    let map: TraceMap = TraceMap::parse("pc=0").unwrap();
+    let left: Vec<TraceRecord> = vec![map.record("0200").unwrap(); 3];
+
+    assert_eq!(
+      TracePair::align(&left, &trace(&[5, 9])),
+      vec![TracePair::Both(0, 0), TracePair::Both(1, 1)]
+    );
+  }
+
+  #[test]
+  fn test_parse_range() {
+    assert_eq!(Tracer::parse_range("0x200-0x2FF"), Ok(0x200..=0x2FF));
+    assert_eq!(Tracer::parse_range("300-3ff"), Ok(0x300..=0x3FF));
+    assert!(Tracer::parse_range("0x2FF-0x200").is_err());
+    assert!(Tracer::parse_range("0x200").is_err());
+  }
+}